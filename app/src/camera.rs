@@ -1,16 +1,36 @@
 use derive_more::derive::*;
 use macroquad::prelude::*;
-use tween::{Linear, TweenValue, Tweener};
+use tween::{Linear, QuadInOut, TweenValue, Tweener};
+
+/// zoom level at startup, screen space always uses it
+pub const DEFAULT_SCALE_EXP: i32 = 2;
+/// how fast a followed target is caught up with, higher is faster
+const FOLLOW_RATE: f32 = 8.;
 
 pub struct CameraWrapper {
     pub scale: f32,
     pub scale_exp: i32,
     pub offset: Vec2f,
     pub scale_tween: Tweener<f32, f32, Linear>,
-    pub offset_tween: Tweener<Vec2f, f32, Linear>,
+    /// zooming keeps the world point under the mouse in place, or the one in the middle
+    pub zoom_at_mouse: bool,
+    pub offset_tween: Tweener<Vec2f, f32, QuadInOut>,
+    /// world position kept in the middle of the screen, stops other panning
+    pub follow: Option<Vec2f>,
+    /// world area the view stays in, centered on it when the view is bigger
+    pub bounds: Option<base::Rect>,
+    pub shake: Shake,
     pub camera: Camera2D,
 }
 
+/// random offsets fading out over the duration
+#[derive(Default, Clone, Copy)]
+pub struct Shake {
+    pub strength: f32,
+    pub duration: f32,
+    pub remaining: f32,
+}
+
 impl Default for CameraWrapper {
     fn default() -> Self {
         Self::new()
@@ -19,17 +39,28 @@ impl Default for CameraWrapper {
 
 impl CameraWrapper {
     pub fn new() -> Self {
-        let scale_exp = 2;
+        let scale_exp = DEFAULT_SCALE_EXP;
         let base2: f32 = 2.;
         let scale = base2.powf(scale_exp as f32);
         let scale_tween = Tweener::linear(scale, scale, 0.);
 
         let offset = Vec2f { x: -160., y: -40. };
-        let offset_tween = Tweener::linear(offset, offset, 0.00);
+        let offset_tween = Tweener::quad_in_out(offset, offset, 0.00);
 
         let camera = Self::create_camera(scale, offset.into());
         set_camera(&camera);
-        CameraWrapper { scale, scale_exp, scale_tween, offset, offset_tween, camera }
+        CameraWrapper {
+            scale,
+            scale_exp,
+            scale_tween,
+            zoom_at_mouse: true,
+            offset,
+            offset_tween,
+            follow: None,
+            bounds: None,
+            shake: Shake::default(),
+            camera,
+        }
     }
 
     pub fn create_camera(scale: f32, offset: Vec2) -> Camera2D {
@@ -46,6 +77,11 @@ impl CameraWrapper {
         }
     }
 
+    /// camera of the screen space, its top left corner is at 0,0
+    pub fn screen_camera() -> Camera2D {
+        Self::create_camera(2f32.powi(DEFAULT_SCALE_EXP), Vec2::ZERO)
+    }
+
     pub fn set(&self) {
         set_camera(&self.camera);
     }
@@ -53,34 +89,92 @@ impl CameraWrapper {
     /// do tweening and stuff
     pub fn process(&mut self) {
         // handle camera
-        let mouse_position = Vec2f::from(mouse_position());
         let time = get_frame_time();
 
-        if !self.offset_tween.is_finished() {
+        if let Some(target) = self.follow {
+            let goal = target - self.half_view(self.scale);
+            let t = 1. - (-FOLLOW_RATE * time).exp();
+            self.offset += (goal - self.offset) * t;
+        } else if !self.offset_tween.is_finished() {
             self.offset = self.offset_tween.move_by(time);
         }
 
         if !self.scale_tween.is_finished() {
-            let point = Vec2f::from(self.camera.screen_to_world(mouse_position.into()));
+            let anchor = if self.zoom_at_mouse {
+                Vec2f::from(mouse_position())
+            } else {
+                Vec2f { x: screen_width() / 2., y: screen_height() / 2. }
+            };
+            let point = Vec2f::from(self.camera.screen_to_world(anchor.into()));
             let new_scale = self.scale_tween.move_by(time);
             let new_camera = Self::create_camera(new_scale, self.offset.into());
-            let new_point = Vec2f::from(new_camera.screen_to_world(mouse_position.into()));
+            let new_point = Vec2f::from(new_camera.screen_to_world(anchor.into()));
             let pan_correction = new_point - point;
             self.offset -= pan_correction;
             self.scale = new_scale;
         }
 
-        self.camera = Self::create_camera(self.scale, self.offset.into());
+        self.clamp_to_bounds();
+        let mut shaken = self.offset;
+        if self.shake.remaining > 0. {
+            self.shake.remaining = (self.shake.remaining - time).max(0.);
+            let strength = self.shake.strength * self.shake.remaining / self.shake.duration;
+            shaken.x += rand::gen_range(-strength, strength);
+            shaken.y += rand::gen_range(-strength, strength);
+        }
+        self.camera = Self::create_camera(self.scale, shaken.into());
         self.set();
         //cw_debug!("Camera scale: {} offset: {:?}", self.scale, self.offset);
         //cw_debug!("Camera scale_exp: {}", self.scale_exp);
     }
 
+    /// half of the world area visible at the given scale
+    fn half_view(&self, scale: f32) -> Vec2f {
+        Vec2f { x: screen_width() / scale, y: screen_height() / scale }
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+        let size = self.half_view(self.scale) * 2.;
+        let view = base::Rect::new(self.offset.x, self.offset.y, size.x, size.y);
+        let view = view.keep_inside(bounds);
+        self.offset = Vec2f { x: view.x, y: view.y };
+    }
+
+    /// pans until x,y is in the middle of the screen, at once without a duration
+    pub fn move_to(&mut self, x: f32, y: f32, duration: f32) {
+        let goal = Vec2f { x, y } - self.half_view(self.scale);
+        self.follow = None;
+        if duration > 0. {
+            self.offset_tween = Tweener::quad_in_out(self.offset, goal, duration);
+        } else {
+            self.offset = goal;
+            self.offset_tween = Tweener::quad_in_out(goal, goal, 0.);
+            self.clamp_to_bounds();
+            self.camera = Self::create_camera(self.scale, self.offset.into());
+        }
+    }
+
+    /// zooms to the given level around the middle of the screen
+    pub fn zoom_to(&mut self, scale_exp: i32, duration: f32) {
+        self.scale_exp = scale_exp.clamp(1, 5);
+        let target = 2f32.powi(self.scale_exp);
+        self.zoom_at_mouse = false;
+        self.scale_tween = Tweener::linear(self.scale, target, duration.max(f32::EPSILON));
+    }
+
+    pub fn shake(&mut self, strength: f32, duration: f32) {
+        self.shake = Shake { strength, duration, remaining: duration };
+    }
+
     pub fn zoom(&mut self, delta: i32) {
         self.scale_exp += delta;
         let base2: f32 = 2.;
         self.scale_exp = self.scale_exp.clamp(1, 5);
         let target = base2.powf(self.scale_exp as f32);
+        self.zoom_at_mouse = true;
         self.scale_tween = Tweener::linear(self.scale, target, 0.25);
     }
 
@@ -90,20 +184,21 @@ impl CameraWrapper {
         self.offset += self.screen_to_world(old) - self.screen_to_world(new);
     }
 
-    pub fn move_camera(&mut self, (x, y): (f32, f32)) {
-        self.offset.x += x;
-        self.offset.y += y;
-    }
-
     pub fn screen_to_world(&self, pos: impl Into<Vec2>) -> Vec2f {
         let pos = pos.into();
         self.camera.screen_to_world(pos).into()
     }
 
-    #[allow(unused)]
-    pub fn world_to_screen(&self, pos: impl Into<Vec2>) -> Vec2f {
-        let pos = pos.into();
-        self.camera.world_to_screen(pos).into()
+    /// world space rect of the whole screen
+    pub fn visible_world_rect(&self) -> Rect {
+        let top_left = self.camera.screen_to_world(vec2(0., 0.));
+        let bottom_right = self.camera.screen_to_world(vec2(screen_width(), screen_height()));
+        Rect::new(
+            top_left.x,
+            top_left.y,
+            bottom_right.x - top_left.x,
+            bottom_right.y - top_left.y,
+        )
     }
 
     pub fn mouse_world(&self) -> Vec2 {
@@ -139,8 +234,8 @@ impl From<Vec2> for Vec2f {
     }
 }
 
-impl Into<Vec2> for Vec2f {
-    fn into(self) -> Vec2 {
-        Vec2 { x: self.x, y: self.y }
+impl From<Vec2f> for Vec2 {
+    fn from(val: Vec2f) -> Self {
+        Vec2 { x: val.x, y: val.y }
     }
 }
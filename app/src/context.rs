@@ -1,17 +1,43 @@
-use std::cell::RefCell;
-
-use base::*;
+use base::{
+    input::{Action, Binding, ClickTracker, InputMap, Key},
+    text::{TextLayout, TextStyle},
+    *,
+};
 
 use macroquad::prelude::*;
 
-use crate::{camera::CameraWrapper, util::texture_store::TextureStore};
+use crate::{
+    camera::{CameraWrapper, Vec2f, DEFAULT_SCALE_EXP},
+    draw::{DrawCommand, DrawOp, DrawStats, OWN_TEXTURE, SHAPES},
+    util::{
+        layer_cache::LayerCache,
+        sound_store::SoundStore,
+        text_cache::{measure_line, TextCache},
+        texture_store::TextureStore,
+        texture_watcher::TextureWatcher,
+    },
+};
+
+/// where rebound keys are stored, relative to the working directory like the assets
+const INPUT_CONFIG: &str = "../config/input.json";
 
 pub struct Context {
-    draw_buffer: RefCell<Vec<DrawCommand>>,
+    draw_buffer: Vec<DrawCommand>,
+    /// space of the following draws, reset to world after each frame
+    space: Space,
+    /// counted before drawing the last frame
+    pub stats: DrawStats,
     pub camera: CameraWrapper,
     pub textures: TextureStore,
     pub loading: Vec<(String, String)>,
+    pub sounds: SoundStore,
+    pub loading_sounds: Vec<(String, String)>,
     pub font: Font,
+    text_cache: TextCache,
+    layer_cache: LayerCache,
+    texture_watcher: TextureWatcher,
+    pub input_map: InputMap,
+    clicks: [ClickTracker; 3],
 }
 
 impl ContextTrait for Context {
@@ -29,14 +55,8 @@ impl ContextTrait for Context {
     }
 
     fn draw_rect(&mut self, rect: base::Rect, c: base::Color, z_level: i32) {
-        let color = macroquad::prelude::Color { r: c.r, g: c.g, b: c.b, a: c.a };
-
-        let command = move || {
-            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
-        };
-        self.draw_buffer
-            .borrow_mut()
-            .push(DrawCommand { z_level, command: Box::new(command) });
+        let op = DrawOp::Rect { rect: to_rect(rect), color: to_color(c) };
+        self.push(z_level, SHAPES, op);
     }
 
     fn draw_rect_lines(
@@ -46,81 +66,62 @@ impl ContextTrait for Context {
         c: base::Color,
         z_level: i32,
     ) {
-        let color = macroquad::prelude::Color { r: c.r, g: c.g, b: c.b, a: c.a };
-
-        let command = move || {
-            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, thickness, color);
-        };
-        self.draw_buffer
-            .borrow_mut()
-            .push(DrawCommand { z_level, command: Box::new(command) });
+        let op = DrawOp::RectLines { rect: to_rect(rect), thickness, color: to_color(c) };
+        self.push(z_level, SHAPES, op);
     }
 
     fn draw_circle(&mut self, circle: base::Circle, c: base::Color, z_level: i32) {
-        let color = macroquad::prelude::Color { r: c.r, g: c.g, b: c.b, a: c.a };
-
-        let command = move || {
-            draw_circle(circle.pos.x, circle.pos.y, circle.radius, color);
-        };
-        self.draw_buffer
-            .borrow_mut()
-            .push(DrawCommand { z_level, command: Box::new(command) });
+        let pos = vec2(circle.pos.x, circle.pos.y);
+        self.push(
+            z_level,
+            SHAPES,
+            DrawOp::Circle { pos, radius: circle.radius, color: to_color(c) },
+        );
     }
 
     fn draw_text(
         &mut self,
         text: &str,
-        size: f32,
+        style: &TextStyle,
         x: f32,
         y: f32,
         z_level: i32,
     ) -> base::Rect {
-        let font = Some(self.font.clone());
-        let zooming = !self.camera.scale_tween.is_finished();
-        let dimens = measure_text(text, Some(&self.font), size as u16, 1.);
-        let text = text.to_string();
-        let command = move || {
-            // TODO this tanks performance during zoom (maybe disable it then/cache it?)
-            let text_params = if zooming {
-                TextParams {
-                    font: font.as_ref(),
-                    font_size: size as u16,
-                    color: DARKGRAY,
-                    ..Default::default()
-                }
-            } else {
-                let (font_size, font_scale, font_aspect) = camera_font_scale(size);
-                TextParams {
-                    font: font.as_ref(),
-                    font_size,
-                    font_scale,
-                    font_scale_aspect: font_aspect,
-                    color: DARKGRAY,
-                    ..Default::default()
-                }
+        let (size, scale_exp) = (style.size, self.text_scale_exp());
+        let font = &self.font;
+        let layout = TextLayout::new(text, style, x, y, |line| {
+            measure_line(line, size, font, scale_exp)
+        });
+        for line in layout.lines.iter().filter(|line| !line.text.is_empty()) {
+            let text =
+                self.text_cache.get(&line.text, size, &self.font, scale_exp, &self.camera);
+            let op = DrawOp::Text {
+                x: line.pos.x,
+                y: line.pos.y - text.offset_y,
+                text,
+                color: to_color(style.color),
+                outline: style.outline.map(to_color),
+                shadow: style.shadow.map(to_color),
+                offset: style.effect_offset(),
             };
-            draw_text_ex(&text, x, y, text_params);
-        };
-        self.draw_buffer
-            .borrow_mut()
-            .push(DrawCommand { z_level, command: Box::new(command) });
-        return base::Rect { x, y: y - dimens.offset_y, w: dimens.width, h: dimens.height };
+            self.push(z_level, OWN_TEXTURE, op);
+        }
+        layout.rect
+    }
+
+    fn measure_text(&self, text: &str, style: &TextStyle) -> base::Rect {
+        let (size, scale_exp) = (style.size, self.text_scale_exp());
+        let measure = |line: &str| measure_line(line, size, &self.font, scale_exp);
+        TextLayout::new(text, style, 0., 0., measure).rect
     }
 
     fn draw_texture(&mut self, name: &str, x: f32, y: f32, z_level: i32) {
-        // load if not in texture store
-        // then add to draw buffer
         if let Some(texture) = self.textures.get(name) {
-            let source = None;
-            let params = DrawTextureParams { source, ..Default::default() };
-            let command = move || {
-                draw_texture_ex(&texture, x, y, WHITE, params);
-            };
-            self.draw_buffer
-                .borrow_mut()
-                .push(DrawCommand { z_level, command: Box::new(command) });
+            let (color, params) = (WHITE, DrawTextureParams::default());
+            let op = DrawOp::TexturePart { texture, x, y, color, params };
+            self.push(z_level, self.textures.id(name), op);
         } else {
-            self.draw_text(&format!("ERROR('{name}')"), 20., x, y, 9999);
+            self.draw_text(&format!("ERROR('{name}')"), &TextStyle::new(20.), x, y, 9999);
         }
     }
 
@@ -132,20 +133,35 @@ impl ContextTrait for Context {
         y: f32,
         z_level: i32,
     ) {
-        // load if not in texture store
-        // then add to draw buffer
+        self.draw_texture_ex(name, src, x, y, &DrawParams::default(), z_level);
+    }
+
+    fn draw_texture_ex(
+        &mut self,
+        name: &str,
+        src: base::Rect,
+        x: f32,
+        y: f32,
+        params: &DrawParams,
+        z_level: i32,
+    ) {
         if let Some(texture) = self.textures.get(name) {
-            let source =
-                Some(macroquad::math::Rect { x: src.x, y: src.y, w: src.w, h: src.h });
-            let params = DrawTextureParams { source, ..Default::default() };
-            let command = move || {
-                draw_texture_ex(&texture, x, y, WHITE, params);
+            let size = params.size.map_or(vec2(src.w, src.h), |s| vec2(s.x, s.y));
+            let size = size * params.scale;
+            let pivot = params.pivot.map_or(size / 2., |p| vec2(p.x, p.y));
+            let texture_params = DrawTextureParams {
+                source: Some(to_rect(src)),
+                dest_size: Some(size),
+                rotation: params.rotation,
+                flip_x: params.flip_x,
+                flip_y: params.flip_y,
+                pivot: Some(vec2(x, y) + pivot),
             };
-            self.draw_buffer
-                .borrow_mut()
-                .push(DrawCommand { z_level, command: Box::new(command) });
+            let color = to_color(params.tint);
+            let op = DrawOp::TexturePart { texture, x, y, color, params: texture_params };
+            self.push(z_level, self.textures.id(name), op);
         } else {
-            self.draw_text(&format!("ERROR('{name}')"), 20., x, y, 9999);
+            self.draw_text(&format!("ERROR('{name}')"), &TextStyle::new(20.), x, y, 9999);
         }
     }
 
@@ -156,21 +172,27 @@ impl ContextTrait for Context {
         target: base::Rect,
         z_level: i32,
     ) {
-        // load if not in texture store
-        // then add to draw buffer
         if let Some(texture) = self.textures.get(name) {
-            let source =
-                Some(macroquad::math::Rect { x: src.x, y: src.y, w: src.w, h: src.h });
-            let dest_size = Some(vec2(target.w, target.h));
-            let params = DrawTextureParams { source, dest_size, ..Default::default() };
-            let command = move || {
-                draw_texture_ex(&texture, target.x, target.y, WHITE, params);
+            let op = DrawOp::TexturePartScaled {
+                texture,
+                src: to_rect(src),
+                target: to_rect(target),
             };
-            self.draw_buffer
-                .borrow_mut()
-                .push(DrawCommand { z_level, command: Box::new(command) });
+            self.push(z_level, self.textures.id(name), op);
         } else {
-            self.draw_text(&format!("ERROR('{name}')"), 20., target.x, target.y, 9999);
+            let style = TextStyle::new(20.);
+            self.draw_text(&format!("ERROR('{name}')"), &style, target.x, target.y, 9999);
+        }
+    }
+
+    fn draw_tile_layer(&mut self, layer: &str, texture: &str, tiles: &[Tile], z_level: i32) {
+        let Some(source) = self.textures.get(texture) else {
+            let style = TextStyle::new(20.);
+            self.draw_text(&format!("ERROR('{texture}')"), &style, 0., 0., 9999);
+            return;
+        };
+        if let Some(baked) = self.layer_cache.get(layer, &source, tiles, &self.camera) {
+            self.push(z_level, OWN_TEXTURE, DrawOp::Layer(baked));
         }
     }
 
@@ -178,30 +200,157 @@ impl ContextTrait for Context {
         self.loading.push((name.to_string(), path.to_string()));
     }
 
-    fn texture_dimensions(&mut self, name: &str) -> base::Rect {
-        self.textures
-            .get(name)
-            .map(|t| base::Rect { x: 0., y: 0., w: t.width(), h: t.width() })
-            .unwrap_or(base::Rect { x: 0., y: 0., w: 0., h: 0. })
+    fn reload_texture(&mut self, name: &str) {
+        match self.textures.path(name) {
+            Some(path) => self.loading.push((name.to_string(), path.to_string())),
+            None => println!("Can't reload {name}, it was never loaded"),
+        }
+    }
+
+    fn texture_dimensions(&self, name: &str) -> Option<base::Rect> {
+        self.textures.get(name).map(|t| base::Rect::wh(t.width(), t.height()))
+    }
+
+    fn texture_status(&self, name: &str) -> TextureStatus {
+        if self.loading.iter().any(|(n, _)| n == name) {
+            TextureStatus::Pending
+        } else if self.textures.get(name).is_some() {
+            TextureStatus::Loaded
+        } else if self.textures.failed(name) {
+            TextureStatus::Failed
+        } else {
+            TextureStatus::Missing
+        }
+    }
+
+    fn load_sound(&mut self, name: &str, path: &str) {
+        self.loading_sounds.push((name.to_string(), path.to_string()));
+    }
+
+    fn play_sound(&mut self, name: &str) {
+        self.sounds.play_sound(name);
+    }
+
+    fn play_music(&mut self, name: &str) {
+        self.sounds.play_music(name);
+    }
+
+    fn stop_music(&mut self) {
+        self.sounds.stop_music();
+    }
+
+    fn set_volume(&mut self, channel: AudioChannel, volume: f32) {
+        let volume = volume.clamp(0., 1.);
+        match channel {
+            AudioChannel::Sfx => self.sounds.sfx_volume = volume,
+            AudioChannel::Music => self.sounds.set_music_volume(volume),
+        }
+    }
+
+    fn volume(&self, channel: AudioChannel) -> f32 {
+        match channel {
+            AudioChannel::Sfx => self.sounds.sfx_volume,
+            AudioChannel::Music => self.sounds.music_volume,
+        }
     }
 
     fn is_pressed(&self, button: Button) -> bool {
-        match button {
-            Button::MouseLeft => is_mouse_button_pressed(MouseButton::Left),
-            Button::MouseMiddle => is_mouse_button_pressed(MouseButton::Middle),
-            Button::MouseRight => is_mouse_button_pressed(MouseButton::Right),
+        is_mouse_button_pressed(to_mouse_button(button))
+    }
+
+    fn is_down(&self, button: Button) -> bool {
+        is_mouse_button_down(to_mouse_button(button))
+    }
+
+    fn is_released(&self, button: Button) -> bool {
+        is_mouse_button_released(to_mouse_button(button))
+    }
+
+    fn drag_delta(&self, button: Button) -> Option<FPos> {
+        self.clicks[button.index()].drag_delta()
+    }
+
+    fn click_count(&self, button: Button) -> u32 {
+        self.clicks[button.index()].click_count()
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        is_key_pressed(to_keycode(key))
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        is_key_down(to_keycode(key))
+    }
+
+    fn is_key_released(&self, key: Key) -> bool {
+        is_key_released(to_keycode(key))
+    }
+
+    fn is_action_pressed(&self, action: Action) -> bool {
+        let clicked = |b: Button| self.clicks[b.index()].is_click();
+        self.input_map.is_active(action, |k| self.is_key_pressed(k), clicked)
+    }
+
+    fn last_input(&self) -> Option<Binding> {
+        let key = Key::ALL.into_iter().find(|key| self.is_key_pressed(*key)).map(Binding::Key);
+        key.or(Button::ALL.into_iter().find(|b| self.is_pressed(*b)).map(Binding::Button))
+    }
+
+    fn input_map(&self) -> InputMap {
+        self.input_map.clone()
+    }
+
+    fn set_input_map(&mut self, map: InputMap) {
+        if let Err(err) = map.save(INPUT_CONFIG) {
+            println!("Error saving {INPUT_CONFIG}: {err}");
         }
+        self.input_map = map;
     }
 
     fn mouse_screen(&self) -> FPos {
-        let m = mouse_position();
-        FPos { x: m.0, y: m.1 }
+        let m = CameraWrapper::screen_camera().screen_to_world(mouse_position().into());
+        FPos { x: m.x, y: m.y }
     }
 
     fn mouse_world(&self) -> FPos {
         let m = self.camera.mouse_world();
         FPos { x: m.x, y: m.y }
     }
+
+    fn visible_world_rect(&self) -> base::Rect {
+        let r = self.camera.visible_world_rect();
+        base::Rect { x: r.x, y: r.y, w: r.w, h: r.h }
+    }
+
+    fn set_space(&mut self, space: Space) {
+        self.space = space;
+    }
+
+    fn move_camera_to(&mut self, pos: FPos, duration: f32) {
+        self.camera.move_to(pos.x, pos.y, duration);
+    }
+
+    fn zoom_camera_to(&mut self, zoom: i32, duration: f32) {
+        self.camera.zoom_to(zoom, duration);
+    }
+
+    fn follow_camera(&mut self, target: Option<FPos>) {
+        self.camera.follow = target.map(|t| Vec2f { x: t.x, y: t.y });
+    }
+
+    fn shake_camera(&mut self, strength: f32, duration: f32) {
+        self.camera.shake(strength, duration);
+    }
+
+    fn set_camera_bounds(&mut self, bounds: Option<base::Rect>) {
+        self.camera.bounds = bounds;
+    }
+
+    fn screen_rect(&self) -> base::Rect {
+        let camera = CameraWrapper::screen_camera();
+        let size = camera.screen_to_world(vec2(screen_width(), screen_height()));
+        base::Rect::wh(size.x, size.y)
+    }
 }
 
 impl Context {
@@ -211,30 +360,117 @@ impl Context {
                 .unwrap();
         Self {
             draw_buffer: Default::default(),
+            space: Space::World,
+            stats: Default::default(),
             camera: Default::default(),
             textures: Default::default(),
             loading: Default::default(),
+            sounds: Default::default(),
+            loading_sounds: Default::default(),
             font,
+            text_cache: Default::default(),
+            layer_cache: Default::default(),
+            texture_watcher: Default::default(),
+            input_map: InputMap::load(INPUT_CONFIG),
+            clicks: Default::default(),
+        }
+    }
+
+    /// tracks drags and double clicks, should be called once per frame before the worker
+    pub fn update_input(&mut self) {
+        let pos = self.mouse_screen();
+        for button in Button::ALL {
+            let pressed = self.is_pressed(button);
+            let released = self.is_released(button);
+            self.clicks[button.index()].update(get_time(), pos, pressed, released);
+        }
+    }
+
+    fn push(&mut self, z_level: i32, batch: usize, op: DrawOp) {
+        self.draw_buffer.push(DrawCommand { space: self.space, z_level, batch, op });
+    }
+
+    /// text is rasterized for the zoom level it is shown at
+    fn text_scale_exp(&self) -> i32 {
+        match self.space {
+            Space::World => self.camera.scale_exp,
+            Space::Screen => DEFAULT_SCALE_EXP,
         }
     }
 
     /// executes deferred drawing, should be called once per frame
     pub async fn process(&mut self) {
+        for name in self.texture_watcher.poll(&self.textures) {
+            self.reload_texture(&name);
+        }
         for (name, path) in self.loading.drain(..) {
-            if let Err(_err) = self.textures.load_texture(&path, name, false).await {
+            let reload = self.textures.get(&name).is_some();
+            if let Err(_err) = self.textures.load_texture(&path, &name, false).await {
+                println!("Error loading {}", &path);
+            } else if reload {
+                // the new texture might get the id of the old one, so hashing can't tell
+                self.layer_cache.clear();
+            }
+        }
+        for (name, path) in self.loading_sounds.drain(..) {
+            if let Err(_err) = self.sounds.load_sound(&path, name).await {
                 println!("Error loading {}", &path);
             }
         }
 
-        let buffer = &mut self.draw_buffer.borrow_mut();
-        buffer.sort_by_key(|it| it.z_level);
+        let buffer = &mut self.draw_buffer;
+        let view = self.camera.visible_world_rect();
+        let recorded = buffer.len();
+        buffer.retain(|it| it.space == Space::Screen || it.op.bounds().overlaps(&view));
+        buffer.sort_by_key(|it| (it.space, it.z_level, it.batch));
+        self.stats = DrawStats { culled: recorded - buffer.len(), ..DrawStats::count(buffer) };
+        self.camera.set();
+        let mut space = Space::World;
         for draw in buffer.drain(..) {
-            (draw.command)();
+            if draw.space != space {
+                space = draw.space;
+                set_camera(&CameraWrapper::screen_camera());
+            }
+            draw.op.execute();
         }
+        self.camera.set();
+        self.space = Space::World;
+        self.text_cache.end_frame();
     }
 }
 
-struct DrawCommand {
-    z_level: i32,
-    command: Box<dyn FnOnce() -> ()>,
+fn to_color(c: base::Color) -> macroquad::prelude::Color {
+    macroquad::prelude::Color { r: c.r, g: c.g, b: c.b, a: c.a }
+}
+
+fn to_rect(r: base::Rect) -> macroquad::math::Rect {
+    macroquad::math::Rect { x: r.x, y: r.y, w: r.w, h: r.h }
+}
+
+fn to_mouse_button(button: Button) -> MouseButton {
+    match button {
+        Button::MouseLeft => MouseButton::Left,
+        Button::MouseMiddle => MouseButton::Middle,
+        Button::MouseRight => MouseButton::Right,
+    }
+}
+
+/// both enums use the same names, so the mapping is generated
+macro_rules! keycode_mapping {
+    ($key:expr, $($name:ident),*) => {
+        match $key {
+            $(Key::$name => KeyCode::$name,)*
+        }
+    };
+}
+
+fn to_keycode(key: Key) -> KeyCode {
+    #[rustfmt::skip]
+    let code = keycode_mapping!(key,
+        Space, Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, A, B, C, D, E, F,
+        G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, Enter, Tab,
+        Backspace, Delete, Right, Left, Down, Up, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10,
+        F11, F12, LeftShift, LeftControl, LeftAlt, RightShift, RightControl, RightAlt
+    );
+    code
 }
@@ -0,0 +1,174 @@
+use base::Space;
+use macroquad::prelude::*;
+
+use crate::util::{layer_cache::BakedLayer, text_cache::CachedText};
+
+/// text is drawn shifted in every direction below the glyphs to outline them
+const OUTLINE_OFFSETS: [(f32, f32); 8] =
+    [(-1., -1.), (0., -1.), (1., -1.), (-1., 0.), (1., 0.), (-1., 1.), (0., 1.), (1., 1.)];
+
+/// a deferred draw call, everything it needs is resolved when it is recorded
+pub enum DrawOp {
+    Rect {
+        rect: Rect,
+        color: Color,
+    },
+    RectLines {
+        rect: Rect,
+        thickness: f32,
+        color: Color,
+    },
+    Circle {
+        pos: Vec2,
+        radius: f32,
+        color: Color,
+    },
+    /// a single line, x and y are its top left corner
+    Text {
+        text: CachedText,
+        x: f32,
+        y: f32,
+        color: Color,
+        outline: Option<Color>,
+        shadow: Option<Color>,
+        /// outline thickness and shadow distance
+        offset: f32,
+    },
+    /// the whole texture if there is no source rect in the params
+    TexturePart {
+        texture: Texture2D,
+        x: f32,
+        y: f32,
+        color: Color,
+        params: DrawTextureParams,
+    },
+    TexturePartScaled {
+        texture: Texture2D,
+        src: Rect,
+        target: Rect,
+    },
+    Layer(BakedLayer),
+}
+
+impl DrawOp {
+    /// world space area the op can touch, used for culling
+    pub fn bounds(&self) -> Rect {
+        match self {
+            DrawOp::Rect { rect, .. } => *rect,
+            DrawOp::RectLines { rect, thickness, .. } => grow(*rect, thickness / 2.),
+            DrawOp::Circle { pos, radius, .. } => {
+                Rect::new(pos.x - radius, pos.y - radius, radius * 2., radius * 2.)
+            }
+            DrawOp::Text { text, x, y, offset, .. } => {
+                grow(Rect::new(*x, *y, text.w, text.h), *offset)
+            }
+            DrawOp::TexturePart { texture, x, y, params, .. } => {
+                let size = params
+                    .dest_size
+                    .or(params.source.map(|src| src.size()))
+                    .unwrap_or(texture.size());
+                let rect = Rect::new(*x, *y, size.x, size.y);
+                if params.rotation == 0. {
+                    return rect;
+                }
+                // a rotated rect stays within the circle around the pivot through the
+                // farthest corner
+                let pivot = params.pivot.unwrap_or(rect.center());
+                let radius =
+                    [rect.point(), rect.point() + size, vec2(rect.x + size.x, rect.y)]
+                        .into_iter()
+                        .chain([vec2(rect.x, rect.y + size.y)])
+                        .map(|corner| corner.distance(pivot))
+                        .fold(0., f32::max);
+                Rect::new(pivot.x - radius, pivot.y - radius, radius * 2., radius * 2.)
+            }
+            DrawOp::TexturePartScaled { target, .. } => *target,
+            DrawOp::Layer(layer) => {
+                let b = layer.bounds;
+                Rect::new(b.x, b.y, b.w, b.h)
+            }
+        }
+    }
+
+    pub fn execute(&self) {
+        match self {
+            DrawOp::Rect { rect, color } => {
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, *color)
+            }
+            DrawOp::RectLines { rect, thickness, color } => {
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, *thickness, *color)
+            }
+            DrawOp::Circle { pos, radius, color } => {
+                draw_circle(pos.x, pos.y, *radius, *color)
+            }
+            DrawOp::Text { text, x, y, color, outline, shadow, offset } => {
+                let draw = |dx: f32, dy: f32, color: Color| text.draw(x + dx, y + dy, color);
+                if let Some(shadow) = shadow {
+                    draw(*offset, *offset, *shadow);
+                }
+                if let Some(outline) = outline {
+                    for (dx, dy) in OUTLINE_OFFSETS {
+                        draw(dx * offset, dy * offset, *outline);
+                    }
+                }
+                draw(0., 0., *color);
+            }
+            DrawOp::TexturePart { texture, x, y, color, params } => {
+                draw_texture_ex(texture, *x, *y, *color, params.clone());
+            }
+            DrawOp::TexturePartScaled { texture, src, target } => {
+                let dest_size = Some(target.size());
+                let params =
+                    DrawTextureParams { source: Some(*src), dest_size, ..Default::default() };
+                draw_texture_ex(texture, target.x, target.y, WHITE, params);
+            }
+            DrawOp::Layer(layer) => {
+                let b = layer.bounds;
+                draw_render_target(&layer.texture, Rect::new(b.x, b.y, b.w, b.h), WHITE);
+            }
+        }
+    }
+}
+
+fn grow(r: Rect, amount: f32) -> Rect {
+    Rect::new(r.x - amount, r.y - amount, r.w + 2. * amount, r.h + 2. * amount)
+}
+
+/// render targets are upside down
+pub fn draw_render_target(texture: &Texture2D, target: Rect, color: Color) {
+    let dest_size = Some(target.size());
+    let params = DrawTextureParams { dest_size, flip_y: true, ..Default::default() };
+    draw_texture_ex(texture, target.x, target.y, color, params);
+}
+
+pub struct DrawCommand {
+    pub space: Space,
+    pub z_level: i32,
+    /// draws of a z level are ordered by batch, so draws of the same texture end up
+    /// next to each other and macroquad can merge them into a single draw call
+    pub batch: usize,
+    pub op: DrawOp,
+}
+
+/// batch of untextured draws, textures use their id in the texture store
+pub const SHAPES: usize = 0;
+/// batch of text and baked layers, each of them has its own texture
+pub const OWN_TEXTURE: usize = usize::MAX;
+
+/// what the last frame drew
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DrawStats {
+    pub commands: usize,
+    /// changes of the batch between consecutive commands, an upper bound of draw calls
+    pub batches: usize,
+    /// skipped because they were outside of the camera
+    pub culled: usize,
+}
+
+impl DrawStats {
+    pub fn count(buffer: &[DrawCommand]) -> Self {
+        let batches = buffer.windows(2).filter(|w| w[0].batch != w[1].batch).count();
+        let batches = batches + (!buffer.is_empty()) as usize;
+        DrawStats { commands: buffer.len(), batches, culled: 0 }
+    }
+}
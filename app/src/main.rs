@@ -9,6 +9,7 @@ use macroquad::prelude::*;
 use notify::{Event, INotifyWatcher, RecursiveMode, Watcher};
 mod camera;
 mod context;
+mod draw;
 mod util;
 
 /// this makes it possible to unload shared libraries even if they use
@@ -17,6 +18,9 @@ mod util;
 ///
 /// see this post for details:
 /// https://fasterthanli.me/articles/so-you-want-to-live-reload-rust
+///
+/// # Safety
+/// never called by us, only exists to shadow the libc symbol
 #[no_mangle]
 pub unsafe extern "C" fn __cxa_thread_atexit_impl() {}
 
@@ -40,36 +44,43 @@ async fn main() {
     loop {
         clear_background(BLACK);
 
-        if is_mouse_button_down(MouseButton::Middle) {
+        if is_mouse_button_down(MouseButton::Middle)
+            || is_mouse_button_down(MouseButton::Right)
+        {
             ctx.camera.mouse_delta(last_mouse_pos, mouse_position());
         }
 
         last_mouse_pos = mouse_position();
-        match mouse_wheel() {
-            (_x, y) => {
-                if y != 0. {
-                    if y > 0. {
-                        ctx.camera.zoom(1);
-                    }
-                    if y < 0. {
-                        ctx.camera.zoom(-1);
-                    }
-                }
+        let (_x, y) = mouse_wheel();
+        if y != 0. {
+            if y > 0. {
+                ctx.camera.zoom(1);
+            }
+            if y < 0. {
+                ctx.camera.zoom(-1);
             }
         }
 
         ctx.camera.process();
+        ctx.update_input();
 
         // let start = Instant::now();
         worker.update(ctx);
         // let duration = start.elapsed();
         // println!("Reload + Execution took: {:?}", duration)));
 
-        ctx.process().await;
+        let fps = ctx.fps().round();
+        let fps = if fps > 55. && fps < 65. { 60. } else { fps };
+        let stats = ctx.stats;
+        let s = format!(
+            "FPS: {fps} draws: {}/{} culled: {}",
+            stats.batches, stats.commands, stats.culled
+        );
+        ctx.set_space(Space::Screen);
+        let style = text::TextStyle::new(10.).color(base::Color::WHITE);
+        ctx.draw_text(&s, &style, 10., 10., i32::MAX);
 
-        let fps = get_fps();
-        let s = format!("FPS: {}", if fps > 55 && fps < 65 { 60 } else { fps });
-        draw_text(&s, 20.0, 20.0, 30.0, WHITE);
+        ctx.process().await;
 
         next_frame().await
     }
@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use base::Tile;
+use macroquad::prelude::*;
+
+use crate::camera::CameraWrapper;
+
+/// a tile layer rendered into a texture with one texel per world unit
+#[derive(Clone)]
+pub struct BakedLayer {
+    pub texture: Texture2D,
+    /// world space area covered by the texture
+    pub bounds: base::Rect,
+    hash: u64,
+}
+
+/// static tile layers cost a single draw call once baked
+#[derive(Default)]
+pub struct LayerCache {
+    layers: HashMap<String, BakedLayer>,
+}
+
+impl LayerCache {
+    /// bakes the layer if it is new or its tiles changed,
+    /// which switches the camera and restores `camera` after
+    pub fn get(
+        &mut self,
+        layer: &str,
+        texture: &Texture2D,
+        tiles: &[Tile],
+        camera: &CameraWrapper,
+    ) -> Option<BakedLayer> {
+        let hash = hash_tiles(texture, tiles);
+        match self.layers.get(layer) {
+            Some(baked) if baked.hash == hash => Some(baked.clone()),
+            _ => {
+                let baked = bake(texture, tiles, hash)?;
+                camera.set();
+                self.layers.insert(layer.to_string(), baked.clone());
+                Some(baked)
+            }
+        }
+    }
+
+    /// every layer is baked again the next time it is drawn
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+}
+
+fn hash_tiles(texture: &Texture2D, tiles: &[Tile]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    texture.raw_miniquad_id().hash(&mut hasher);
+    for tile in tiles {
+        let r = tile.source_rect;
+        for v in [r.x, r.y, r.w, r.h, tile.pos.x, tile.pos.y] {
+            v.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// None for empty layers
+fn bake(texture: &Texture2D, tiles: &[Tile], hash: u64) -> Option<BakedLayer> {
+    let bounds = tiles
+        .iter()
+        .map(|t| base::Rect::new(t.pos.x, t.pos.y, t.source_rect.w, t.source_rect.h))
+        .reduce(|a, b| a.fuse(b))?;
+    let (w, h) = (bounds.w.ceil().max(1.), bounds.h.ceil().max(1.));
+
+    let target = render_target(w as u32, h as u32);
+    target.texture.set_filter(FilterMode::Nearest);
+    let mut camera = Camera2D::from_display_rect(Rect::new(bounds.x, bounds.y, w, h));
+    camera.render_target = Some(target.clone());
+    set_camera(&camera);
+    clear_background(Color::new(0., 0., 0., 0.));
+    for tile in tiles {
+        let src = tile.source_rect;
+        let source = Some(Rect::new(src.x, src.y, src.w, src.h));
+        let params = DrawTextureParams { source, ..Default::default() };
+        draw_texture_ex(texture, tile.pos.x, tile.pos.y, WHITE, params);
+    }
+
+    let bounds = base::Rect { w, h, ..bounds };
+    Some(BakedLayer { texture: target.texture, bounds, hash })
+}
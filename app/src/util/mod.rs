@@ -1 +1,5 @@
+pub mod layer_cache;
+pub mod sound_store;
+pub mod text_cache;
 pub mod texture_store;
+pub mod texture_watcher;
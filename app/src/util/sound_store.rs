@@ -0,0 +1,103 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "audio")]
+use macroquad::audio::{
+    load_sound, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound,
+};
+
+/// without the `audio` feature nothing is played, this keeps the build free of alsa
+#[cfg(not(feature = "audio"))]
+#[derive(Clone)]
+pub struct Sound;
+
+/// named sounds plus the music track that is currently looping
+pub struct SoundStore {
+    sounds: HashMap<String, Sound>,
+    /// requested track, it starts as soon as it is loaded
+    music: Option<String>,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    /// only complain once about every unknown sound
+    missing: HashSet<String>,
+}
+
+impl Default for SoundStore {
+    fn default() -> Self {
+        Self {
+            sounds: HashMap::new(),
+            music: None,
+            sfx_volume: 1.,
+            music_volume: 1.,
+            missing: HashSet::new(),
+        }
+    }
+}
+
+impl SoundStore {
+    pub async fn load_sound(
+        &mut self,
+        path: impl AsRef<str>,
+        name: impl Into<String>,
+    ) -> Result<(), macroquad::Error> {
+        #[cfg(feature = "audio")]
+        let sound = load_sound(path.as_ref()).await?;
+        #[cfg(not(feature = "audio"))]
+        let sound = {
+            let _ = path;
+            Sound
+        };
+        let name = name.into();
+        // music requested while it was loading starts now, reloaded music starts over
+        let is_music = self.music.as_ref() == Some(&name);
+        if is_music {
+            self.stop_music();
+        }
+        self.sounds.insert(name.clone(), sound);
+        if is_music {
+            self.play_music(&name);
+        }
+        Ok(())
+    }
+
+    pub fn play_sound(&mut self, name: &str) {
+        if let Some(_sound) = self.get(name) {
+            #[cfg(feature = "audio")]
+            play_sound(&_sound, PlaySoundParams { looped: false, volume: self.sfx_volume });
+        }
+    }
+
+    pub fn play_music(&mut self, name: &str) {
+        if self.music.as_deref() == Some(name) {
+            return;
+        }
+        self.stop_music();
+        self.music = Some(name.to_string());
+        if let Some(_sound) = self.sounds.get(name) {
+            #[cfg(feature = "audio")]
+            play_sound(_sound, PlaySoundParams { looped: true, volume: self.music_volume });
+        }
+    }
+
+    pub fn stop_music(&mut self) {
+        if let Some(_sound) = self.music.take().and_then(|name| self.sounds.get(&name)) {
+            #[cfg(feature = "audio")]
+            stop_sound(_sound);
+        }
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.music_volume = volume;
+        if let Some(_sound) = self.music.as_ref().and_then(|name| self.sounds.get(name)) {
+            #[cfg(feature = "audio")]
+            set_sound_volume(_sound, volume);
+        }
+    }
+
+    fn get(&mut self, name: &str) -> Option<Sound> {
+        let sound = self.sounds.get(name).cloned();
+        if sound.is_none() && self.missing.insert(name.to_string()) {
+            println!("Sound not loaded: {name}");
+        }
+        sound
+    }
+}
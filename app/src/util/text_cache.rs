@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::{camera::CameraWrapper, draw::draw_render_target};
+
+/// text at the pixel size of a zoom level, the pixel size is rounded like when rendered
+#[derive(Clone)]
+pub struct CachedText {
+    pub image: TextImage,
+    /// world space dimensions
+    pub w: f32,
+    pub h: f32,
+    /// world space distance from the top of the text to its baseline
+    pub offset_y: f32,
+}
+
+#[derive(Clone)]
+pub enum TextImage {
+    /// rendered once, drawn like any other texture
+    Rendered(Texture2D),
+    /// drawn through the font atlas, for text that isn't cached (yet)
+    Direct { text: String, font: Font, font_size: u16, scale: f32 },
+}
+
+impl CachedText {
+    /// draws the text with its top left corner at x,y
+    pub fn draw(&self, x: f32, y: f32, color: Color) {
+        match &self.image {
+            TextImage::Rendered(texture) => {
+                let target = Rect::new(x, y, self.w, self.h);
+                draw_render_target(texture, target, color);
+            }
+            TextImage::Direct { text, font, font_size, scale } => {
+                let params = TextParams {
+                    font: Some(font),
+                    font_size: *font_size,
+                    font_scale: 1. / scale,
+                    color,
+                    ..Default::default()
+                };
+                draw_text_ex(text, x, y + self.offset_y, params);
+            }
+        }
+    }
+}
+
+struct Entry {
+    text: CachedText,
+    last_used: u64,
+}
+
+/// rasterizing text through the font atlas every frame is slow while zooming,
+/// so every (text, size, scale_exp) that is drawn in consecutive frames is rendered once
+/// into its own render target
+/// text that changes every frame, like counters, never gets there and is drawn directly
+#[derive(Default)]
+pub struct TextCache {
+    /// keyed by (size bits, scale_exp) first, so lookups don't allocate the text
+    entries: HashMap<(u32, i32), HashMap<String, Entry>>,
+    /// frame texts were last drawn directly in, they are rendered when drawn again later
+    seen: HashMap<(u32, i32), HashMap<String, u64>>,
+    len: usize,
+    /// rendered this frame, every render switches the camera
+    renders: usize,
+    frame: u64,
+}
+
+impl TextCache {
+    /// entries not drawn for this many frames are dropped
+    const MAX_AGE: u64 = 120;
+    /// render targets kept at most, the least recently used one makes room for new ones
+    const MAX_ENTRIES: usize = 256;
+    /// further misses in a frame are drawn directly
+    const MAX_RENDERS_PER_FRAME: usize = 8;
+
+    /// renders the text on a miss, which switches the camera and restores `camera` after
+    pub fn get(
+        &mut self,
+        text: &str,
+        size: f32,
+        font: &Font,
+        scale_exp: i32,
+        camera: &CameraWrapper,
+    ) -> CachedText {
+        let frame = self.frame;
+        let key = (size.to_bits(), scale_exp);
+        if let Some(entry) = self.entries.get_mut(&key).and_then(|texts| texts.get_mut(text)) {
+            entry.last_used = frame;
+            return entry.text.clone();
+        }
+
+        let seen = self.seen.get(&key).and_then(|texts| texts.get(text));
+        let seen_before = seen.is_some_and(|last| *last < frame);
+        if !seen_before || self.renders >= Self::MAX_RENDERS_PER_FRAME || !self.make_room() {
+            self.seen.entry(key).or_default().insert(text.to_string(), frame);
+            return direct(text, size, font, scale_exp);
+        }
+        self.seen.entry(key).or_default().remove(text);
+
+        let cached = render(text, size, font, scale_exp);
+        camera.set();
+        self.renders += 1;
+        self.len += 1;
+        let texts = self.entries.entry(key).or_default();
+        texts.insert(text.to_string(), Entry { text: cached.clone(), last_used: frame });
+        cached
+    }
+
+    /// drops the least recently used entry when full, unless all were used this frame
+    fn make_room(&mut self) -> bool {
+        if self.len < Self::MAX_ENTRIES {
+            return true;
+        }
+        let oldest = self
+            .entries
+            .iter()
+            .flat_map(|(key, texts)| {
+                texts.iter().map(move |(text, e)| (e.last_used, key, text))
+            })
+            .filter(|(last_used, ..)| *last_used < self.frame)
+            .min_by_key(|(last_used, ..)| *last_used)
+            .map(|(_, key, text)| (*key, text.clone()));
+        let Some((key, text)) = oldest else {
+            return false;
+        };
+        if let Some(texts) = self.entries.get_mut(&key) {
+            texts.remove(&text);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// drops stale entries, should be called once per frame
+    pub fn end_frame(&mut self) {
+        let frame = self.frame;
+        for texts in self.entries.values_mut() {
+            texts.retain(|_, entry| frame - entry.last_used < Self::MAX_AGE);
+        }
+        self.entries.retain(|_, texts| !texts.is_empty());
+        self.len = self.entries.values().map(HashMap::len).sum();
+        // only texts drawn last frame can be rendered next frame
+        for texts in self.seen.values_mut() {
+            texts.retain(|_, last| *last == frame);
+        }
+        self.seen.retain(|_, texts| !texts.is_empty());
+        self.renders = 0;
+        self.frame += 1;
+    }
+}
+
+/// world space rect of a single line with its baseline starting at 0,0,
+/// measured at the pixel size the cache renders it with
+pub fn measure_line(text: &str, size: f32, font: &Font, scale_exp: i32) -> base::Rect {
+    let scale = 2f32.powi(scale_exp);
+    let dimens = measure_text(text, Some(font), font_size(size, scale), 1.);
+    let (w, h) = (dimens.width.ceil().max(1.), dimens.height.ceil().max(1.));
+    base::Rect { x: 0., y: -dimens.offset_y / scale, w: w / scale, h: h / scale }
+}
+
+fn font_size(size: f32, scale: f32) -> u16 {
+    (size * scale).round() as u16
+}
+
+fn direct(text: &str, size: f32, font: &Font, scale_exp: i32) -> CachedText {
+    let scale = 2f32.powi(scale_exp);
+    let font_size = font_size(size, scale);
+    let dimens = measure_text(text, Some(font), font_size, 1.);
+    let font = font.clone();
+    CachedText {
+        image: TextImage::Direct { text: text.to_string(), font, font_size, scale },
+        w: dimens.width.ceil().max(1.) / scale,
+        h: dimens.height.ceil().max(1.) / scale,
+        offset_y: dimens.offset_y / scale,
+    }
+}
+
+/// white text, the color is applied as tint when drawing
+fn render(text: &str, size: f32, font: &Font, scale_exp: i32) -> CachedText {
+    let scale = 2f32.powi(scale_exp);
+    let font_size = font_size(size, scale);
+    let dimens = measure_text(text, Some(font), font_size, 1.);
+    let w = dimens.width.ceil().max(1.);
+    let h = dimens.height.ceil().max(1.);
+
+    let target = render_target(w as u32, h as u32);
+    target.texture.set_filter(FilterMode::Linear);
+    let mut camera = Camera2D::from_display_rect(Rect::new(0., 0., w, h));
+    camera.render_target = Some(target.clone());
+    set_camera(&camera);
+    clear_background(Color::new(1., 1., 1., 0.));
+    let params =
+        TextParams { font: Some(font), font_size, color: WHITE, ..Default::default() };
+    draw_text_ex(text, 0., dimens.offset_y, params);
+
+    CachedText {
+        image: TextImage::Rendered(target.texture),
+        w: w / scale,
+        h: h / scale,
+        offset_y: dimens.offset_y / scale,
+    }
+}
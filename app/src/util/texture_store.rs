@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use macroquad::texture::{load_texture, FilterMode, Texture2D};
 use std::result::Result;
@@ -6,6 +6,12 @@ use std::result::Result;
 #[derive(Default)]
 pub struct TextureStore {
     textures: HashMap<String, Texture2D>,
+    /// stable small ids starting at 1, used to group draws of the same texture
+    ids: HashMap<String, usize>,
+    /// names whose last load failed
+    failed: HashSet<String>,
+    /// where every texture was loaded from, kept for reloading
+    paths: HashMap<String, String>,
 }
 
 impl TextureStore {
@@ -15,15 +21,44 @@ impl TextureStore {
         name: impl Into<String>,
         antialias: bool,
     ) -> Result<(), macroquad::Error> {
-        let texture = load_texture(path.as_ref()).await?;
+        let name = name.into();
+        self.paths.insert(name.clone(), path.as_ref().to_string());
+        let texture = match load_texture(path.as_ref()).await {
+            Ok(texture) => texture,
+            Err(err) => {
+                self.failed.insert(name);
+                return Err(err);
+            }
+        };
         if !antialias {
             texture.set_filter(FilterMode::Nearest);
         }
-        self.textures.insert(name.into(), texture);
+        self.failed.remove(&name);
+        let next_id = self.ids.len() + 1;
+        self.ids.entry(name.clone()).or_insert(next_id);
+        self.textures.insert(name, texture);
         Ok(())
     }
 
     pub fn get(&self, name: impl AsRef<str>) -> Option<Texture2D> {
         self.textures.get(name.as_ref()).cloned()
     }
+
+    pub fn path(&self, name: impl AsRef<str>) -> Option<&str> {
+        self.paths.get(name.as_ref()).map(|it| it.as_str())
+    }
+
+    /// (name, path) of every texture that was loaded or tried to
+    pub fn paths(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.paths.iter().map(|(name, path)| (name.as_str(), path.as_str()))
+    }
+
+    pub fn failed(&self, name: impl AsRef<str>) -> bool {
+        self.failed.contains(name.as_ref())
+    }
+
+    /// 0 for unknown textures
+    pub fn id(&self, name: impl AsRef<str>) -> usize {
+        self.ids.get(name.as_ref()).copied().unwrap_or(0)
+    }
 }
@@ -0,0 +1,82 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver},
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::texture_store::TextureStore;
+
+/// reports textures whose files changed on disk
+/// directories are watched instead of files, editors often replace a file when saving
+pub struct TextureWatcher {
+    watcher: Option<RecommendedWatcher>,
+    receiver: Receiver<Result<Event, notify::Error>>,
+    watched_dirs: HashSet<PathBuf>,
+    /// canonical path of every watched texture to its name
+    files: HashMap<PathBuf, String>,
+    /// path of every texture name as it was last seen in the store
+    known: HashMap<String, String>,
+}
+
+impl Default for TextureWatcher {
+    fn default() -> Self {
+        let (tx, receiver) = channel();
+        let watcher = notify::recommended_watcher(tx)
+            .inspect_err(|err| println!("Texture hot reloading disabled: {err}"))
+            .ok();
+        Self {
+            watcher,
+            receiver,
+            watched_dirs: HashSet::new(),
+            files: HashMap::new(),
+            known: HashMap::new(),
+        }
+    }
+}
+
+impl TextureWatcher {
+    /// starts watching newly loaded textures and returns the names of changed ones
+    pub fn poll(&mut self, store: &TextureStore) -> Vec<String> {
+        for (name, path) in store.paths() {
+            if self.known.get(name).map(|it| it.as_str()) == Some(path) {
+                continue;
+            }
+            self.known.insert(name.to_string(), path.to_string());
+            self.files.retain(|_, it| it != name);
+            let Ok(path) = std::fs::canonicalize(path) else {
+                continue;
+            };
+            let dir = path.parent().unwrap().to_path_buf();
+            if let Some(watcher) = &mut self.watcher {
+                if !self.watched_dirs.contains(&dir) {
+                    match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                        Ok(()) => _ = self.watched_dirs.insert(dir),
+                        Err(err) => println!("Error watching {}: {err}", dir.display()),
+                    }
+                }
+            }
+            self.files.insert(path, name.to_string());
+        }
+
+        // a save usually fires several events, every texture is reloaded once
+        let mut changed = Vec::new();
+        while let Ok(event) = self.receiver.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            if !(event.kind.is_modify() || event.kind.is_create()) {
+                continue;
+            }
+            for path in event.paths {
+                if let Some(name) = self.files.get(&path) {
+                    if !changed.contains(name) {
+                        changed.push(name.clone());
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
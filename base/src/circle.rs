@@ -17,6 +17,6 @@ impl Circle {
         let lhs = dist_x - rect.w / 2.0;
         let rhs = dist_y - rect.h / 2.0;
         let dist_sq = (lhs * lhs) + (rhs * rhs);
-        return dist_sq <= self.radius * self.radius;
+        dist_sq <= self.radius * self.radius
     }
 }
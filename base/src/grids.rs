@@ -1,10 +1,16 @@
 // vendored
 #![allow(dead_code)]
-use std::ops::{Add, Index, IndexMut, Mul};
+use std::{
+    fmt::Display,
+    ops::{Add, Index, IndexMut, Mul},
+    str::{Chars, FromStr},
+};
+
+use nanoserde::{DeJson, DeJsonErr, DeJsonState, SerJson};
 
 use crate::Pos;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SerJson)]
 pub struct Grid<T> {
     pub data: Vec<T>,
     pub width: i32,
@@ -116,7 +122,7 @@ impl<T: Clone> Grid<T> {
         self.data.iter_mut().enumerate().map(|(i, v)| {
             let i = i as i32;
             let x = i % self.width;
-            let y = i / self.height;
+            let y = i / self.width;
 
             (x, y, v)
         })
@@ -149,7 +155,7 @@ impl<T: Clone> Grid<T> {
     /// Returns an iterator over the rows of the grid.
     ///
     /// ```
-    /// use grids::Grid;
+    /// use base::grids::Grid;
     ///
     /// let mut grid = Grid::new(3, 2, 0);
     ///
@@ -293,6 +299,394 @@ impl<T: Clone> Grid<T> {
             }
         }
     }
+
+    /// applies `f` to every value and returns the results as a new grid
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        let data = self.data.iter().map(f).collect();
+        Grid { data, width: self.width, height: self.height }
+    }
+
+    /// combines each value with the value at the same coordinate in the other grid
+    ///
+    /// panics if dimensions don't match
+    pub fn zip_with<R, O, F: FnMut(&T, &R) -> O>(&self, other: &Grid<R>, mut f: F) -> Grid<O> {
+        self.ensure_dimensions_match(other);
+        let data = self.data.iter().zip(other.data.iter()).map(|(l, r)| f(l, r)).collect();
+        Grid { data, width: self.width, height: self.height }
+    }
+
+    /// boolean grid which is true wherever the predicate holds
+    pub fn mask<F: FnMut(&T) -> bool>(&self, mut f: F) -> Grid<bool> {
+        self.map(|v| f(v))
+    }
+
+    /// keeps the values where the mask is true and replaces everything else with `fallback`
+    ///
+    /// panics if dimensions don't match
+    pub fn select(&self, mask: &Grid<bool>, fallback: T) -> Grid<T> {
+        self.zip_with(mask, |v, keep| if *keep { v.clone() } else { fallback.clone() })
+    }
+
+    /// the smaller value of both grids at each coordinate
+    ///
+    /// panics if dimensions don't match
+    pub fn min(&self, other: &Grid<T>) -> Grid<T>
+    where
+        T: PartialOrd<T>,
+    {
+        self.zip_with(other, |l, r| if r < l { r.clone() } else { l.clone() })
+    }
+
+    /// the bigger value of both grids at each coordinate
+    ///
+    /// panics if dimensions don't match
+    pub fn max(&self, other: &Grid<T>) -> Grid<T>
+    where
+        T: PartialOrd<T>,
+    {
+        self.zip_with(other, |l, r| if r > l { r.clone() } else { l.clone() })
+    }
+
+    /// borrowed rectangular part of the grid starting at `min`
+    /// the view is shrunk so it never reaches outside of the grid
+    pub fn view(&self, min: Pos, width: i32, height: i32) -> GridView<'_, T> {
+        let x = min.x.clamp(0, self.width);
+        let y = min.y.clamp(0, self.height);
+        let width = (min.x + width).clamp(x, self.width) - x;
+        let height = (min.y + height).clamp(y, self.height) - y;
+        GridView { grid: self, x, y, width, height }
+    }
+
+    /// copy of the rectangular part of the grid starting at `min`
+    /// cells outside of the original grid are set to `fill`
+    pub fn crop(&self, min: Pos, width: i32, height: i32, fill: T) -> Grid<T> {
+        Grid::filled_with(width, height, |x, y| {
+            let pos = Pos::new(min.x + x, min.y + y);
+            if self.is_valid(pos) {
+                self[pos].clone()
+            } else {
+                fill.clone()
+            }
+        })
+    }
+
+    /// copy with new dimensions, keeping content anchored in the top left
+    /// new cells are set to `fill`
+    pub fn resized(&self, width: i32, height: i32, fill: T) -> Grid<T> {
+        self.crop(Pos::new(0, 0), width, height, fill)
+    }
+
+    /// all valid positions with a manhattan distance of exactly `radius` to `center`
+    pub fn iter_ring(&self, center: Pos, radius: i32) -> impl Iterator<Item = (Pos, &T)> {
+        let mut coords = vec![];
+
+        if radius == 0 {
+            coords.push(center);
+        }
+        for i in 0..radius.max(0) {
+            let (cx, cy) = (center.x, center.y);
+            coords.push(Pos::new(cx + radius - i, cy + i));
+            coords.push(Pos::new(cx - i, cy + radius - i));
+            coords.push(Pos::new(cx - radius + i, cy - i));
+            coords.push(Pos::new(cx + i, cy - radius + i));
+        }
+
+        coords.into_iter().filter(|pos| self.is_valid(*pos)).map(|pos| (pos, &self[pos]))
+    }
+
+    /// all valid positions with `min_radius <= manhattan distance <= max_radius`
+    /// ordered from the inner most ring to the outer most
+    pub fn iter_diamond(
+        &self,
+        center: Pos,
+        min_radius: i32,
+        max_radius: i32,
+    ) -> impl Iterator<Item = (Pos, &T)> {
+        (min_radius.max(0)..=max_radius).flat_map(move |r| self.iter_ring(center, r))
+    }
+
+    /// walks the bresenham line from `from` to `to` (both inclusive)
+    /// stops at the first position outside of the grid
+    pub fn iter_line(&self, from: Pos, to: Pos) -> impl Iterator<Item = (Pos, &T)> {
+        line(from, to).take_while(|pos| self.is_valid(*pos)).map(|pos| (pos, &self[pos]))
+    }
+
+    /// all positions reachable from `start` through orthogonal steps over passable cells
+    /// `start` itself is only part of the result if it is passable
+    pub fn flood_fill<F: FnMut(Pos, &T) -> bool>(
+        &self,
+        start: Pos,
+        mut passable: F,
+    ) -> Grid<bool> {
+        let mut filled = Grid::new(self.width, self.height, false);
+        let mut next = vec![start];
+
+        while let Some(pos) = next.pop() {
+            if !self.is_valid(pos) || filled[pos] || !passable(pos, &self[pos]) {
+                continue;
+            }
+            filled[pos] = true;
+            let Pos { x, y } = pos;
+            next.extend([(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].map(Pos::from));
+        }
+
+        filled
+    }
+
+    /// true if no cell strictly between `from` and `to` blocks the view
+    /// the endpoints themselves never block, so a unit standing in a forest can still be shot
+    ///
+    /// bresenham lines are not symmetric, so the line is checked in both directions
+    /// and either one being clear is enough
+    pub fn is_visible<F: Fn(Pos, &T) -> bool>(&self, from: Pos, to: Pos, blocks: F) -> bool {
+        if !self.is_valid(from) || !self.is_valid(to) {
+            return false;
+        }
+        let clear = |a: Pos, b: Pos| {
+            line(a, b).filter(|pos| *pos != a && *pos != b).all(|pos| !blocks(pos, &self[pos]))
+        };
+        clear(from, to) || clear(to, from)
+    }
+
+    /// all positions within the given manhattan range that are visible from `center`
+    /// useful for finding targets of ranged attacks
+    pub fn visible_in_range<F: Fn(Pos, &T) -> bool>(
+        &self,
+        center: Pos,
+        min_radius: i32,
+        max_radius: i32,
+        blocks: F,
+    ) -> Vec<Pos> {
+        self.iter_diamond(center, min_radius, max_radius)
+            .map(|(pos, _)| pos)
+            .filter(|pos| self.is_visible(center, *pos, &blocks))
+            .collect()
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// parses a grid from text with one char per cell and one line per row
+    /// surrounding whitespace and empty lines are ignored, so boards can be indented in tests
+    ///
+    /// ```
+    /// use base::grids::Grid;
+    ///
+    /// let grid = Grid::from_ascii(
+    ///     "
+    ///     #..
+    ///     .#.
+    ///     ",
+    ///     |c| c == '#',
+    /// )
+    /// .unwrap();
+    /// assert_eq!(3, grid.width());
+    /// assert!(grid[(1, 1)]);
+    /// ```
+    pub fn from_ascii<F: FnMut(char) -> T>(text: &str, mut f: F) -> Result<Self, String> {
+        let rows: Vec<Vec<T>> =
+            text_rows(text).map(|line| line.chars().map(&mut f).collect()).collect();
+        Self::from_rows(rows)
+    }
+
+    /// writes one char per cell and one line per row, inverse of [Grid::from_ascii]
+    pub fn to_ascii<F: FnMut(&T) -> char>(&self, mut f: F) -> String {
+        let mut result = String::with_capacity(self.data.len() + self.height as usize);
+        for row in self.row_iter() {
+            result.extend(row.map(&mut f));
+            result.push('\n');
+        }
+        result
+    }
+
+    /// parses whitespace separated values with one line per row,
+    /// inverse of [Grid::to_text]
+    pub fn from_text(text: &str) -> Result<Self, String>
+    where
+        T: FromStr,
+    {
+        let mut rows = Vec::new();
+        for line in text_rows(text) {
+            let row = line
+                .split_whitespace()
+                .map(|v| {
+                    v.parse::<T>().map_err(|_| format!("Can't parse '{v}' in line '{line}'"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+        Self::from_rows(rows)
+    }
+
+    /// writes the values separated by spaces with one line per row
+    /// columns are right aligned, so the output is readable in test failures
+    pub fn to_text(&self) -> String
+    where
+        T: Display,
+    {
+        let cells = self.map(|v| v.to_string());
+        let cell_width = cells.iter_values().map(|v| v.chars().count()).max().unwrap_or(0);
+        let mut result = String::new();
+        for row in cells.row_iter() {
+            let row: Vec<String> = row.map(|v| format!("{v:>cell_width$}")).collect();
+            result.push_str(&row.join(" "));
+            result.push('\n');
+        }
+        result
+    }
+
+    fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, String> {
+        let height = rows.len() as i32;
+        let width = rows.first().map(|row| row.len()).unwrap_or(0) as i32;
+        if let Some(row) = rows.iter().position(|row| row.len() as i32 != width) {
+            return Err(format!(
+                "Row {row} has {} cells, but the first row has {width}",
+                rows[row].len()
+            ));
+        }
+        let data = rows.into_iter().flatten().collect();
+        Ok(Grid { data, width, height })
+    }
+}
+
+/// non empty lines with surrounding whitespace trimmed
+fn text_rows(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(|line| line.trim()).filter(|line| !line.is_empty())
+}
+
+impl<T: Clone + Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_text())
+    }
+}
+
+/// mirrors the serialized form of [Grid], so the dimensions can be checked after loading
+#[derive(DeJson)]
+struct GridJson<T> {
+    data: Vec<T>,
+    width: i32,
+    height: i32,
+}
+
+impl<T: DeJson> DeJson for Grid<T> {
+    fn de_json(state: &mut DeJsonState, input: &mut Chars) -> Result<Self, DeJsonErr> {
+        let GridJson { data, width, height } = GridJson::de_json(state, input)?;
+        if width < 0 || height < 0 || data.len() != (width * height) as usize {
+            return Err(state.err_range("grid data does not match width * height"));
+        }
+        Ok(Grid { data, width, height })
+    }
+}
+
+impl Grid<bool> {
+    /// true where both masks are true
+    pub fn and(&self, other: &Grid<bool>) -> Grid<bool> {
+        self.zip_with(other, |l, r| *l && *r)
+    }
+
+    /// true where at least one of the masks is true
+    pub fn or(&self, other: &Grid<bool>) -> Grid<bool> {
+        self.zip_with(other, |l, r| *l || *r)
+    }
+
+    /// true where the mask is false
+    pub fn invert(&self) -> Grid<bool> {
+        self.map(|v| !v)
+    }
+}
+
+/// borrowed rectangular part of a grid, see [Grid::view]
+/// coordinates are relative to the top left corner of the view
+#[derive(Clone, Copy, Debug)]
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl<'a, T: Clone> GridView<'a, T> {
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// position of the top left corner of the view in the underlying grid
+    pub fn offset(&self) -> Pos {
+        Pos::new(self.x, self.y)
+    }
+
+    pub fn is_valid(&self, coord: Pos) -> bool {
+        coord.x >= 0 && coord.x < self.width && coord.y >= 0 && coord.y < self.height
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> &'a T {
+        assert!(self.is_valid(Pos::new(x, y)), "({x}, {y}) is outside of the view");
+        &self.grid[(self.x + x, self.y + y)]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32, &'a T)> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| (x, y, self.get(x, y))))
+    }
+
+    pub fn iter_coords(&self) -> impl Iterator<Item = (Pos, &'a T)> + '_ {
+        self.iter().map(|(x, y, v)| (Pos::new(x, y), v))
+    }
+
+    /// copies the viewed cells into their own grid
+    pub fn to_grid(&self) -> Grid<T> {
+        Grid::filled_with(self.width, self.height, |x, y| self.get(x, y).clone())
+    }
+}
+
+/// iterator over the positions of a bresenham line, see [line]
+pub struct Line {
+    pos: Pos,
+    to: Pos,
+    dx: i32,
+    dy: i32,
+    sx: i32,
+    sy: i32,
+    err: i32,
+    done: bool,
+}
+
+/// bresenham line from `from` to `to`, both ends are included
+pub fn line(from: Pos, to: Pos) -> Line {
+    let dx = (to.x - from.x).abs();
+    let dy = -(to.y - from.y).abs();
+    let sx = if from.x < to.x { 1 } else { -1 };
+    let sy = if from.y < to.y { 1 } else { -1 };
+    Line { pos: from, to, dx, dy, sx, sy, err: dx + dy, done: false }
+}
+
+impl Iterator for Line {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let current = self.pos;
+        if current == self.to {
+            self.done = true;
+            return Some(current);
+        }
+        let e2 = 2 * self.err;
+        if e2 >= self.dy {
+            self.err += self.dy;
+            self.pos.x += self.sx;
+        }
+        if e2 <= self.dx {
+            self.err += self.dx;
+            self.pos.y += self.sy;
+        }
+        Some(current)
+    }
 }
 
 impl<T: Clone, Pos: Into<(i32, i32)>> Index<Pos> for Grid<T> {
@@ -385,6 +779,7 @@ fn test_row_iter_single_row() {
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_row_iter_multiple_rows() {
     let grid = Grid::filled_with(3, 3, |x, y| x + y);
     let mut row_iter = grid.row_iter();
@@ -456,3 +851,232 @@ fn test_grid_add() {
     let c = a.add(&b);
     assert_eq!(3.5, c[(2, 2)]);
 }
+
+#[test]
+fn test_ring_and_diamond() {
+    let grid = Grid::new(9, 9, 0);
+    let center = Pos::new(4, 4);
+
+    assert_eq!(vec![center], grid.iter_ring(center, 0).map(|(p, _)| p).collect::<Vec<_>>());
+
+    let ring: Vec<Pos> = grid.iter_ring(center, 2).map(|(p, _)| p).collect();
+    assert_eq!(8, ring.len());
+    assert!(ring.iter().all(|p| p.manhattan(center) == 2));
+    assert!(ring.contains(&Pos::new(6, 4)));
+    assert!(ring.contains(&Pos::new(5, 5)));
+    assert!(ring.contains(&Pos::new(4, 2)));
+
+    // rings get cut off at the border
+    let corner: Vec<Pos> = grid.iter_ring(Pos::new(0, 0), 1).map(|(p, _)| p).collect();
+    assert_eq!(2, corner.len());
+
+    // 4 + 8 + 12 cells with a distance of 1 to 3
+    let diamond: Vec<Pos> = grid.iter_diamond(center, 1, 3).map(|(p, _)| p).collect();
+    assert_eq!(24, diamond.len());
+    assert!(!diamond.contains(&center));
+    assert_eq!(1, diamond[0].manhattan(center));
+    assert_eq!(3, diamond[23].manhattan(center));
+}
+
+#[test]
+fn test_line() {
+    let straight: Vec<Pos> = line(Pos::new(0, 0), Pos::new(3, 0)).collect();
+    assert_eq!(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0), Pos::new(3, 0)], straight);
+
+    let diagonal: Vec<Pos> = line(Pos::new(2, 2), Pos::new(0, 0)).collect();
+    assert_eq!(vec![Pos::new(2, 2), Pos::new(1, 1), Pos::new(0, 0)], diagonal);
+
+    let steep: Vec<Pos> = line(Pos::new(0, 0), Pos::new(1, 4)).collect();
+    assert_eq!(5, steep.len());
+    assert_eq!(Pos::new(1, 4), *steep.last().unwrap());
+
+    let single: Vec<Pos> = line(Pos::new(1, 1), Pos::new(1, 1)).collect();
+    assert_eq!(vec![Pos::new(1, 1)], single);
+
+    // iterating on the grid stops at the border
+    let grid = Grid::new(3, 3, 0);
+    assert_eq!(3, grid.iter_line(Pos::new(0, 1), Pos::new(5, 1)).count());
+}
+
+#[test]
+fn test_flood_fill() {
+    // a wall in column 2 with a gap in the last row
+    let grid = Grid::filled_with(5, 4, |x, y| x == 2 && y < 3);
+    let filled = grid.flood_fill(Pos::new(0, 0), |_, wall| !wall);
+    assert!(filled[(1, 2)]);
+    assert!(filled[(4, 0)]);
+    assert!(!filled[(2, 0)]);
+
+    // closing the gap
+    let grid = Grid::filled_with(5, 4, |x, _| x == 2);
+    let filled = grid.flood_fill(Pos::new(0, 0), |_, wall| !wall);
+    assert!(filled[(1, 3)]);
+    assert!(!filled[(3, 0)]);
+    assert_eq!(8, filled.iter_values().filter(|v| **v).count());
+
+    // starting on a blocked cell fills nothing
+    let filled = grid.flood_fill(Pos::new(2, 0), |_, wall| !wall);
+    assert!(filled.iter_values().all(|v| !v));
+}
+
+#[test]
+fn test_visibility() {
+    // a single blocking cell in the middle
+    let grid = Grid::filled_with(5, 5, |x, y| x == 2 && y == 2);
+    let blocks = |_: Pos, b: &bool| *b;
+
+    assert!(!grid.is_visible(Pos::new(0, 2), Pos::new(4, 2), blocks));
+    assert!(grid.is_visible(Pos::new(0, 0), Pos::new(4, 0), blocks));
+    // the blocking cell itself is visible
+    assert!(grid.is_visible(Pos::new(0, 2), Pos::new(2, 2), blocks));
+    // out of bounds is never visible
+    assert!(!grid.is_visible(Pos::new(0, 2), Pos::new(7, 2), blocks));
+
+    let targets = grid.visible_in_range(Pos::new(0, 2), 3, 4, blocks);
+    assert!(!targets.contains(&Pos::new(3, 2)));
+    assert!(!targets.contains(&Pos::new(4, 2)));
+    assert!(targets.contains(&Pos::new(2, 1)));
+    assert!(targets.contains(&Pos::new(3, 1)));
+}
+
+#[test]
+fn test_map_and_zip() {
+    let grid = Grid::filled_with(3, 2, |x, y| x + y * 3);
+    let doubled = grid.map(|v| v * 2);
+    assert_eq!(vec![0, 2, 4, 6, 8, 10], doubled.data);
+
+    let as_str = grid.map(|v| v.to_string());
+    assert_eq!("5", as_str[(2, 1)]);
+
+    let sum = grid.zip_with(&doubled, |a, b| a + b);
+    assert_eq!(15, sum[(2, 1)]);
+
+    let lower = grid.min(&Grid::new(3, 2, 2));
+    assert_eq!(vec![0, 1, 2, 2, 2, 2], lower.data);
+    let upper = grid.max(&Grid::new(3, 2, 2));
+    assert_eq!(vec![2, 2, 2, 3, 4, 5], upper.data);
+}
+
+#[test]
+fn test_mask_select() {
+    // the same masking as in test_grid_mul, but without going through 0/1 values
+    let mask_source = Grid::filled_with(3, 3, |x, y| if y > 0 { 0 } else { x + 9 });
+    let value_grid = Grid::filled_with(3, 3, |x, y| x + y);
+
+    let mask = mask_source.mask(|v| *v > 0);
+    let result = value_grid.select(&mask, 0);
+
+    #[rustfmt::skip]
+    let expected_data = vec![
+        0, 1, 2,
+        0, 0, 0,
+        0, 0, 0
+    ];
+    assert_eq!(expected_data, result.data);
+
+    // negative values survive the masking, unlike multiplying with a clamped grid
+    let negative = value_grid.map(|v| -v).select(&mask, 0);
+    assert_eq!(-2, negative[(2, 0)]);
+
+    let left = Grid::filled_with(3, 3, |x, _| x == 0);
+    assert_eq!(1, mask.and(&left).iter_values().filter(|v| **v).count());
+    assert_eq!(5, mask.or(&left).iter_values().filter(|v| **v).count());
+    assert_eq!(6, mask.invert().iter_values().filter(|v| **v).count());
+}
+
+#[test]
+fn test_view() {
+    let grid = Grid::filled_with(4, 4, |x, y| x + y * 4);
+    let view = grid.view(Pos::new(1, 2), 2, 2);
+    assert_eq!(2, view.width());
+    assert_eq!(9, *view.get(0, 0));
+    assert_eq!(14, *view.get(1, 1));
+    assert_eq!(vec![9, 10, 13, 14], view.to_grid().data);
+    assert_eq!(Some((Pos::new(1, 1), &14)), view.iter_coords().last());
+
+    // views get clamped to the grid
+    let view = grid.view(Pos::new(3, -1), 5, 2);
+    assert_eq!(1, view.width());
+    assert_eq!(1, view.height());
+    assert_eq!(Pos::new(3, 0), view.offset());
+    assert_eq!(3, *view.get(0, 0));
+}
+
+#[test]
+fn test_crop_and_resize() {
+    let grid = Grid::filled_with(3, 3, |x, y| x + y * 3);
+
+    let cropped = grid.crop(Pos::new(1, 1), 3, 2, -1);
+    #[rustfmt::skip]
+    let expected_data = vec![
+        4, 5, -1,
+        7, 8, -1,
+    ];
+    assert_eq!(expected_data, cropped.data);
+    assert_eq!(3, cropped.width());
+    assert_eq!(2, cropped.height());
+
+    let bigger = grid.resized(4, 4, 0);
+    assert_eq!(8, bigger[(2, 2)]);
+    assert_eq!(0, bigger[(3, 3)]);
+
+    let smaller = grid.resized(2, 1, 0);
+    assert_eq!(vec![0, 1], smaller.data);
+}
+
+#[test]
+fn test_iter_mut_coords() {
+    // wider than high and higher than wide, the row comes from the width in both
+    for (width, height) in [(4, 2), (2, 5)] {
+        let mut grid = Grid::new(width, height, (0, 0));
+        for (x, y, v) in grid.iter_mut() {
+            *v = (x, y);
+        }
+        assert_eq!(grid.iter().map(|(x, y, _)| (x, y)).collect::<Vec<_>>(), grid.data);
+        assert_eq!((width - 1, height - 1), grid[(width - 1, height - 1)]);
+    }
+}
+
+#[test]
+fn test_json_roundtrip() {
+    let grid = Grid::filled_with(3, 2, |x, y| x * 10 + y);
+    let json = grid.serialize_json();
+    let loaded: Grid<i32> = Grid::deserialize_json(&json).unwrap();
+    assert_eq!(grid.data, loaded.data);
+    assert_eq!(3, loaded.width());
+    assert_eq!(2, loaded.height());
+
+    let broken = r#"{"data":[1,2,3],"width":2,"height":2}"#;
+    assert!(Grid::<i32>::deserialize_json(broken).is_err());
+}
+
+#[test]
+fn test_ascii_roundtrip() {
+    let board = "
+        ~~..
+        ~.#.
+    ";
+    let grid = Grid::from_ascii(board, |c| c).unwrap();
+    assert_eq!(4, grid.width());
+    assert_eq!(2, grid.height());
+    assert_eq!('#', grid[(2, 1)]);
+    assert_eq!("~~..\n~.#.\n", grid.to_ascii(|c| *c));
+
+    assert!(Grid::from_ascii("..\n...", |c| c).is_err());
+    let empty = Grid::from_ascii("", |c| c).unwrap();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_text_roundtrip() {
+    let grid = Grid::filled_with(3, 2, |x, y| x * 10 - y);
+    let text = grid.to_text();
+    assert_eq!(" 0 10 20\n-1  9 19\n", text);
+    assert_eq!(text, grid.to_string());
+
+    let loaded: Grid<i32> = Grid::from_text(&text).unwrap();
+    assert_eq!(grid.data, loaded.data);
+
+    assert!(Grid::<i32>::from_text("1 2\n3 x").is_err());
+    assert!(Grid::<i32>::from_text("1 2\n3").is_err());
+}
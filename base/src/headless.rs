@@ -0,0 +1,880 @@
+//! ContextTrait implementation without window or GPU, used for automated tests
+
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use crate::{
+    input::{Action, Binding, ClickTracker, InputMap, Key},
+    text::{TextLayout, TextStyle},
+    AudioChannel, Button, Circle, Color, ContextTrait, DrawParams, FPos, Rect, Space,
+    TextureStatus, Tile,
+};
+
+/// a fixed width font without descent, every char is 0.8 * size wide
+fn measure_line(line: &str, size: f32) -> Rect {
+    Rect::new(0., -size, line.chars().count() as f32 * size * 0.8, size)
+}
+
+/// a single draw call as it was issued to [HeadlessContext]
+/// text is recorded per line with x and y at the start of its baseline
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCall {
+    Rect { rect: Rect, color: Color },
+    RectLines { rect: Rect, thickness: f32, color: Color },
+    Circle { circle: Circle, color: Color },
+    Text { text: String, size: f32, x: f32, y: f32, color: Color },
+    Texture { name: String, x: f32, y: f32 },
+    TexturePart { name: String, src: Rect, x: f32, y: f32, params: DrawParams },
+    TexturePartScaled { name: String, src: Rect, target: Rect },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedDraw {
+    pub space: Space,
+    pub z_level: i32,
+    /// draws of a z level are ordered by batch like in the real context, see [SHAPES]
+    pub batch: usize,
+    pub call: DrawCall,
+}
+
+/// batch of untextured draws, textures use their id, see [HeadlessContext::add_texture]
+pub const SHAPES: usize = 0;
+/// batch of text and tile layers, the real context gives each of them its own texture
+pub const OWN_TEXTURE: usize = usize::MAX;
+
+impl Display for DrawCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn rect(r: &Rect) -> String {
+            format!("({} {} {} {})", r.x, r.y, r.w, r.h)
+        }
+        fn color(c: &Color) -> String {
+            format!("rgba({} {} {} {})", c.r, c.g, c.b, c.a)
+        }
+        match self {
+            DrawCall::Rect { rect: r, color: c } => write!(f, "rect {} {}", rect(r), color(c)),
+            DrawCall::RectLines { rect: r, thickness, color: c } => {
+                write!(f, "rect_lines {} {thickness} {}", rect(r), color(c))
+            }
+            DrawCall::Circle { circle, color: c } => {
+                let Circle { pos, radius } = circle;
+                write!(f, "circle ({} {}) {radius} {}", pos.x, pos.y, color(c))
+            }
+            DrawCall::Text { text, size, x, y, color: c } => {
+                write!(f, "text {text:?} {size} {} at ({x} {y})", color(c))
+            }
+            DrawCall::Texture { name, x, y } => write!(f, "texture {name} at ({x} {y})"),
+            DrawCall::TexturePart { name, src, x, y, params } => {
+                write!(f, "texture {name} {} at ({x} {y})", rect(src))?;
+                // only what differs from the defaults, plain draws keep their short format
+                let p = params;
+                if p.tint != Color::WHITE {
+                    write!(f, " tint {}", color(&p.tint))?;
+                }
+                if p.flip_x || p.flip_y {
+                    write!(f, " flip ({} {})", p.flip_x, p.flip_y)?;
+                }
+                if p.rotation != 0. {
+                    write!(f, " rotation {}", p.rotation)?;
+                }
+                if let Some(pivot) = p.pivot {
+                    write!(f, " pivot ({} {})", pivot.x, pivot.y)?;
+                }
+                if let Some(size) = p.size {
+                    write!(f, " size ({} {})", size.x, size.y)?;
+                }
+                if p.scale != 1. {
+                    write!(f, " scale {}", p.scale)?;
+                }
+                Ok(())
+            }
+            DrawCall::TexturePartScaled { name, src, target } => {
+                write!(f, "texture {name} {} to {}", rect(src), rect(target))
+            }
+        }
+    }
+}
+
+/// an audio call as it was issued to [HeadlessContext]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioCall {
+    Sound(String),
+    Music(String),
+    StopMusic,
+}
+
+/// records draw calls and replays scripted input
+///
+/// input set before a frame is seen by that frame,
+/// button presses only last for a single frame like in the real context
+pub struct HeadlessContext {
+    pub time: f64,
+    pub delta: f32,
+    pub mouse_screen: FPos,
+    pub mouse_world: FPos,
+    /// reported as visible world rect, nothing is culled though
+    pub view: Rect,
+    pub screen: Rect,
+    /// camera calls move and resize the view at once
+    pub zoom: i32,
+    pub follow: Option<FPos>,
+    pub camera_bounds: Option<Rect>,
+    /// (strength, duration) of the last shake, it doesn't move the view
+    pub shake: Option<(f32, f32)>,
+    /// space of the following draws, reset to world at the start of a frame
+    pub space: Space,
+    /// buttons pressed this frame
+    pub pressed: Vec<Button>,
+    /// buttons held down until released
+    pub held: Vec<Button>,
+    /// buttons released this frame
+    pub released: Vec<Button>,
+    pub clicks: [ClickTracker; 3],
+    /// keys pressed this frame
+    pub pressed_keys: Vec<Key>,
+    /// keys held down until released
+    pub held_keys: Vec<Key>,
+    /// keys released this frame
+    pub released_keys: Vec<Key>,
+    pub input_map: InputMap,
+    /// draw calls of the current (or last finished) frame in issue order
+    pub draws: Vec<RecordedDraw>,
+    /// dimensions of known textures, see [HeadlessContext::add_texture]
+    pub textures: HashMap<String, Rect>,
+    /// ids starting at 1 in the order textures were first added, used as batch
+    pub texture_ids: HashMap<String, usize>,
+    /// (name, path) of every load_texture call, they stay pending until added or failed
+    pub loaded: Vec<(String, String)>,
+    /// names of textures reported as failed to load
+    pub failed: Vec<String>,
+    /// names of every reload_texture call
+    pub reloaded: Vec<String>,
+    /// (name, path) of every load_sound call
+    pub loaded_sounds: Vec<(String, String)>,
+    /// audio calls of all frames, music that keeps playing is only recorded once
+    pub audio: Vec<AudioCall>,
+    pub music: Option<String>,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub frame: u64,
+}
+
+impl Default for HeadlessContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadlessContext {
+    pub fn new() -> Self {
+        Self {
+            time: 0.,
+            delta: 1. / 60.,
+            mouse_screen: FPos { x: 0., y: 0. },
+            mouse_world: FPos { x: 0., y: 0. },
+            view: Rect::wh(1280., 720.),
+            screen: Rect::wh(640., 360.),
+            zoom: 2,
+            follow: None,
+            camera_bounds: None,
+            shake: None,
+            space: Space::World,
+            pressed: Vec::new(),
+            held: Vec::new(),
+            released: Vec::new(),
+            clicks: Default::default(),
+            pressed_keys: Vec::new(),
+            held_keys: Vec::new(),
+            released_keys: Vec::new(),
+            input_map: InputMap::default(),
+            draws: Vec::new(),
+            textures: HashMap::new(),
+            texture_ids: HashMap::new(),
+            loaded: Vec::new(),
+            failed: Vec::new(),
+            reloaded: Vec::new(),
+            loaded_sounds: Vec::new(),
+            audio: Vec::new(),
+            music: None,
+            sfx_volume: 1.,
+            music_volume: 1.,
+            frame: 0,
+        }
+    }
+
+    /// makes a texture known, so draws with it are recorded and dimensions are reported
+    pub fn add_texture(&mut self, name: &str, w: f32, h: f32) {
+        self.textures.insert(name.to_string(), Rect::wh(w, h));
+        let next_id = self.texture_ids.len() + 1;
+        self.texture_ids.entry(name.to_string()).or_insert(next_id);
+    }
+
+    /// moves the mouse, screen and world coordinates are treated as identical
+    pub fn set_mouse(&mut self, pos: FPos) {
+        self.mouse_screen = pos;
+        self.mouse_world = pos;
+    }
+
+    /// the button is clicked during the next frame, it is pressed and released at once
+    pub fn press(&mut self, button: Button) {
+        self.pressed.push(button);
+        self.released.push(button);
+    }
+
+    /// the button is pressed during the next frame and stays down until released
+    pub fn hold(&mut self, button: Button) {
+        self.pressed.push(button);
+        self.held.push(button);
+    }
+
+    /// the button is reported as released during the next frame
+    pub fn release(&mut self, button: Button) {
+        self.held.retain(|b| *b != button);
+        self.released.push(button);
+    }
+
+    /// the key is pressed and down during the next frame only
+    pub fn press_key(&mut self, key: Key) {
+        self.pressed_keys.push(key);
+    }
+
+    /// the key is pressed during the next frame and stays down until released
+    pub fn hold_key(&mut self, key: Key) {
+        self.pressed_keys.push(key);
+        self.held_keys.push(key);
+    }
+
+    /// the key is reported as released during the next frame
+    pub fn release_key(&mut self, key: Key) {
+        self.held_keys.retain(|k| *k != key);
+        self.released_keys.push(key);
+    }
+
+    /// runs `f` for a single frame, see [HeadlessContext::run_frames]
+    pub fn frame<F: FnMut(&mut Self)>(&mut self, f: F) {
+        self.run_frames(1, f);
+    }
+
+    /// runs `f` once per frame for `n` frames
+    /// draws are cleared at the start of each frame and presses at the end
+    pub fn run_frames<F: FnMut(&mut Self)>(&mut self, n: usize, mut f: F) {
+        for _ in 0..n {
+            self.draws.clear();
+            self.space = Space::World;
+            for button in Button::ALL {
+                let pressed = self.pressed.contains(&button);
+                let released = self.released.contains(&button);
+                let tracker = &mut self.clicks[button.index()];
+                tracker.update(self.time, self.mouse_screen, pressed, released);
+            }
+            f(self);
+            self.pressed.clear();
+            self.released.clear();
+            self.pressed_keys.clear();
+            self.released_keys.clear();
+            self.time += self.delta as f64;
+            self.frame += 1;
+        }
+    }
+
+    /// names of the sound effects played so far
+    pub fn sounds_played(&self) -> Vec<&str> {
+        self.audio
+            .iter()
+            .filter_map(|it| match it {
+                AudioCall::Sound(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// draws in the order the real context would execute them
+    pub fn draws_sorted(&self) -> Vec<&RecordedDraw> {
+        let mut draws: Vec<&RecordedDraw> = self.draws.iter().collect();
+        draws.sort_by_key(|it| (it.space, it.z_level, it.batch));
+        draws
+    }
+
+    /// the sorted draw list of the last frame with one draw call per line
+    pub fn snapshot(&self) -> String {
+        let mut result = String::new();
+        for draw in self.draws_sorted() {
+            let space = if draw.space == Space::Screen { "screen " } else { "" };
+            result.push_str(&format!("z={} {space}{}\n", draw.z_level, draw.call));
+        }
+        result
+    }
+
+    /// draw calls of the given texture
+    pub fn texture_draws(&self, name: &str) -> impl Iterator<Item = &RecordedDraw> {
+        let name = name.to_string();
+        self.draws.iter().filter(move |it| match &it.call {
+            DrawCall::Texture { name: n, .. }
+            | DrawCall::TexturePart { name: n, .. }
+            | DrawCall::TexturePartScaled { name: n, .. } => *n == name,
+            _ => false,
+        })
+    }
+
+    fn push(&mut self, z_level: i32, call: DrawCall) {
+        let batch = match &call {
+            DrawCall::Rect { .. } | DrawCall::RectLines { .. } | DrawCall::Circle { .. } => {
+                SHAPES
+            }
+            DrawCall::Text { .. } => OWN_TEXTURE,
+            DrawCall::Texture { name, .. }
+            | DrawCall::TexturePart { name, .. }
+            | DrawCall::TexturePartScaled { name, .. } => {
+                self.texture_ids.get(name).copied().unwrap_or(SHAPES)
+            }
+        };
+        self.draws.push(RecordedDraw { space: self.space, z_level, batch, call });
+    }
+
+    /// unknown textures are drawn as an error text, same as in the real context
+    fn texture_or_error(&mut self, name: &str, x: f32, y: f32, call: DrawCall, z_level: i32) {
+        if self.textures.contains_key(name) {
+            self.push(z_level, call);
+        } else {
+            let style = TextStyle::new(20.);
+            self.draw_text(&format!("ERROR('{name}')"), &style, x, y, 9999);
+        }
+    }
+}
+
+impl ContextTrait for HeadlessContext {
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    fn fps(&self) -> f32 {
+        1. / self.delta
+    }
+
+    fn draw_rect(&mut self, rect: Rect, color: Color, z_level: i32) {
+        self.push(z_level, DrawCall::Rect { rect, color });
+    }
+
+    fn draw_rect_lines(&mut self, rect: Rect, thickness: f32, color: Color, z_level: i32) {
+        self.push(z_level, DrawCall::RectLines { rect, thickness, color });
+    }
+
+    fn draw_circle(&mut self, circle: Circle, color: Color, z_level: i32) {
+        self.push(z_level, DrawCall::Circle { circle, color });
+    }
+
+    /// there is no font, so every char is assumed to be `0.8 * size` wide
+    fn draw_text(
+        &mut self,
+        text: &str,
+        style: &TextStyle,
+        x: f32,
+        y: f32,
+        z_level: i32,
+    ) -> Rect {
+        let size = style.size;
+        let layout = TextLayout::new(text, style, x, y, |line| measure_line(line, size));
+        let offset = style.effect_offset();
+        for line in layout.lines.iter().filter(|line| !line.text.is_empty()) {
+            let FPos { x, y } = line.pos;
+            let text = line.text.clone();
+            if let Some(color) = style.shadow {
+                let (x, y) = (x + offset, y + offset);
+                self.push(z_level, DrawCall::Text { text: text.clone(), size, x, y, color });
+            }
+            if let Some(color) = style.outline {
+                self.push(z_level, DrawCall::Text { text: text.clone(), size, x, y, color });
+            }
+            self.push(z_level, DrawCall::Text { text, size, x, y, color: style.color });
+        }
+        layout.rect
+    }
+
+    fn measure_text(&self, text: &str, style: &TextStyle) -> Rect {
+        let size = style.size;
+        TextLayout::new(text, style, 0., 0., |line| measure_line(line, size)).rect
+    }
+
+    fn draw_texture(&mut self, name: &str, x: f32, y: f32, z_level: i32) {
+        let call = DrawCall::Texture { name: name.to_string(), x, y };
+        self.texture_or_error(name, x, y, call, z_level);
+    }
+
+    fn draw_texture_part(&mut self, name: &str, src: Rect, x: f32, y: f32, z_level: i32) {
+        self.draw_texture_ex(name, src, x, y, &DrawParams::default(), z_level);
+    }
+
+    fn draw_texture_part_scaled(&mut self, name: &str, src: Rect, target: Rect, z_level: i32) {
+        let call = DrawCall::TexturePartScaled { name: name.to_string(), src, target };
+        self.texture_or_error(name, target.x, target.y, call, z_level);
+    }
+
+    /// recorded tile by tile, so batching doesn't show up in snapshots
+    fn draw_texture_ex(
+        &mut self,
+        name: &str,
+        src: Rect,
+        x: f32,
+        y: f32,
+        params: &DrawParams,
+        z_level: i32,
+    ) {
+        let params = *params;
+        let call = DrawCall::TexturePart { name: name.to_string(), src, x, y, params };
+        self.texture_or_error(name, x, y, call, z_level);
+    }
+
+    fn draw_tile_layer(&mut self, _layer: &str, texture: &str, tiles: &[Tile], z_level: i32) {
+        let first = self.draws.len();
+        for tile in tiles {
+            self.draw_texture_part(texture, tile.source_rect, tile.pos.x, tile.pos.y, z_level);
+        }
+        // baked into a single texture by the real context
+        for draw in &mut self.draws[first..] {
+            draw.batch = OWN_TEXTURE;
+        }
+    }
+
+    fn load_texture(&mut self, name: &str, path: &str) {
+        self.loaded.push((name.to_string(), path.to_string()));
+    }
+
+    fn reload_texture(&mut self, name: &str) {
+        self.reloaded.push(name.to_string());
+    }
+
+    fn texture_dimensions(&self, name: &str) -> Option<Rect> {
+        self.textures.get(name).cloned()
+    }
+
+    fn texture_status(&self, name: &str) -> TextureStatus {
+        if self.textures.contains_key(name) {
+            TextureStatus::Loaded
+        } else if self.failed.iter().any(|it| it == name) {
+            TextureStatus::Failed
+        } else if self.loaded.iter().any(|(n, _)| n == name) {
+            TextureStatus::Pending
+        } else {
+            TextureStatus::Missing
+        }
+    }
+
+    fn load_sound(&mut self, name: &str, path: &str) {
+        self.loaded_sounds.push((name.to_string(), path.to_string()));
+    }
+
+    fn play_sound(&mut self, name: &str) {
+        self.audio.push(AudioCall::Sound(name.to_string()));
+    }
+
+    fn play_music(&mut self, name: &str) {
+        if self.music.as_deref() != Some(name) {
+            self.music = Some(name.to_string());
+            self.audio.push(AudioCall::Music(name.to_string()));
+        }
+    }
+
+    fn stop_music(&mut self) {
+        if self.music.take().is_some() {
+            self.audio.push(AudioCall::StopMusic);
+        }
+    }
+
+    fn set_volume(&mut self, channel: AudioChannel, volume: f32) {
+        let volume = volume.clamp(0., 1.);
+        match channel {
+            AudioChannel::Sfx => self.sfx_volume = volume,
+            AudioChannel::Music => self.music_volume = volume,
+        }
+    }
+
+    fn volume(&self, channel: AudioChannel) -> f32 {
+        match channel {
+            AudioChannel::Sfx => self.sfx_volume,
+            AudioChannel::Music => self.music_volume,
+        }
+    }
+
+    fn is_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    fn is_down(&self, button: Button) -> bool {
+        self.pressed.contains(&button) || self.held.contains(&button)
+    }
+
+    fn is_released(&self, button: Button) -> bool {
+        self.released.contains(&button)
+    }
+
+    fn drag_delta(&self, button: Button) -> Option<FPos> {
+        self.clicks[button.index()].drag_delta()
+    }
+
+    fn click_count(&self, button: Button) -> u32 {
+        self.clicks[button.index()].click_count()
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.pressed_keys.contains(&key) || self.held_keys.contains(&key)
+    }
+
+    fn is_key_released(&self, key: Key) -> bool {
+        self.released_keys.contains(&key)
+    }
+
+    fn is_action_pressed(&self, action: Action) -> bool {
+        let clicked = |b: Button| self.clicks[b.index()].is_click();
+        self.input_map.is_active(action, |k| self.is_key_pressed(k), clicked)
+    }
+
+    fn last_input(&self) -> Option<Binding> {
+        let key = self.pressed_keys.first().map(|key| Binding::Key(*key));
+        key.or(self.pressed.first().map(|button| Binding::Button(*button)))
+    }
+
+    fn input_map(&self) -> InputMap {
+        self.input_map.clone()
+    }
+
+    fn set_input_map(&mut self, map: InputMap) {
+        self.input_map = map;
+    }
+
+    fn mouse_screen(&self) -> FPos {
+        self.mouse_screen
+    }
+
+    fn mouse_world(&self) -> FPos {
+        self.mouse_world
+    }
+
+    fn visible_world_rect(&self) -> Rect {
+        self.view
+    }
+
+    fn move_camera_to(&mut self, pos: FPos, _duration: f32) {
+        self.follow = None;
+        self.view.x = pos.x - self.view.w / 2.;
+        self.view.y = pos.y - self.view.h / 2.;
+        if let Some(bounds) = self.camera_bounds {
+            self.view = self.view.keep_inside(bounds);
+        }
+    }
+
+    fn zoom_camera_to(&mut self, zoom: i32, _duration: f32) {
+        let zoom = zoom.clamp(1, 5);
+        let center = self.view.center();
+        let factor = 2f32.powi(self.zoom - zoom);
+        self.view.w *= factor;
+        self.view.h *= factor;
+        self.zoom = zoom;
+        self.move_camera_to(center, 0.);
+    }
+
+    fn follow_camera(&mut self, target: Option<FPos>) {
+        if let Some(target) = target {
+            self.move_camera_to(target, 0.);
+        }
+        self.follow = target;
+    }
+
+    fn shake_camera(&mut self, strength: f32, duration: f32) {
+        self.shake = Some((strength, duration));
+    }
+
+    fn set_camera_bounds(&mut self, bounds: Option<Rect>) {
+        self.camera_bounds = bounds;
+        if let Some(bounds) = bounds {
+            self.view = self.view.keep_inside(bounds);
+        }
+    }
+
+    fn set_space(&mut self, space: Space) {
+        self.space = space;
+    }
+
+    fn screen_rect(&self) -> Rect {
+        self.screen
+    }
+}
+
+/// compares `actual` with the golden file at `path`
+///
+/// missing golden files are written and the test passes, unless the `CI` env var is set
+/// set `UPDATE_SNAPSHOTS=1` to overwrite golden files after intended changes
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) if !update => expected,
+        Ok(_) => return write_snapshot(path, actual),
+        Err(_) => {
+            assert!(
+                std::env::var_os("CI").is_none(),
+                "Snapshot {} is missing",
+                path.display()
+            );
+            return write_snapshot(path, actual);
+        }
+    };
+    if expected == actual {
+        return;
+    }
+
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let mut diff = String::new();
+    for i in 0..expected_lines.len().max(actual_lines.len()) {
+        match (expected_lines.get(i), actual_lines.get(i)) {
+            (Some(e), Some(a)) if e == a => {}
+            (e, a) => {
+                if let Some(e) = e {
+                    diff.push_str(&format!("{:>5} - {e}\n", i + 1));
+                }
+                if let Some(a) = a {
+                    diff.push_str(&format!("{:>5} + {a}\n", i + 1));
+                }
+            }
+        }
+    }
+    panic!(
+        "Snapshot {} does not match (- expected, + actual):\n{diff}\
+         rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+        path.display()
+    );
+}
+
+fn write_snapshot(path: &Path, actual: &str) {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    std::fs::write(path, actual).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{HAlign, VAlign};
+
+    #[test]
+    fn records_frames() {
+        let mut c = HeadlessContext::new();
+        c.add_texture("tiles", 16., 16.);
+        c.press(Button::MouseLeft);
+
+        let mut presses = Vec::new();
+        c.run_frames(3, |c| {
+            presses.push(c.is_pressed(Button::MouseLeft));
+            c.draw_texture_part("tiles", Rect::wh(16., 16.), 0., 0., 5);
+            c.draw_rect(Rect::wh(1., 1.), Color::rgb(1., 0., 0.), 1);
+        });
+
+        assert_eq!(vec![true, false, false], presses);
+        assert_eq!(3, c.frame);
+        assert!((c.time - 3. / 60.).abs() < 0.0001);
+        // only the last frame is kept
+        assert_eq!(2, c.draws.len());
+        assert_eq!(1, c.draws_sorted()[0].z_level);
+        assert_eq!(1, c.texture_draws("tiles").count());
+    }
+
+    #[test]
+    fn keys_and_actions() {
+        let mut c = HeadlessContext::new();
+        let state = |c: &HeadlessContext| {
+            (
+                c.is_key_pressed(Key::W),
+                c.is_key_down(Key::W),
+                c.is_key_released(Key::W),
+                c.is_action_pressed(Action::CursorUp),
+            )
+        };
+        let mut frames = Vec::new();
+
+        c.hold_key(Key::W);
+        c.frame(|c| frames.push(state(c)));
+        c.frame(|c| frames.push(state(c)));
+        c.release_key(Key::W);
+        c.frame(|c| frames.push(state(c)));
+        c.frame(|c| frames.push(state(c)));
+
+        #[rustfmt::skip]
+        let expected = vec![
+            (true, true, false, true),
+            (false, true, false, false),
+            (false, false, true, false),
+            (false, false, false, false),
+        ];
+        assert_eq!(expected, frames);
+
+        // a tap is only down for a single frame
+        c.press_key(Key::Enter);
+        c.frame(|c| {
+            assert!(c.is_key_down(Key::Enter) && c.is_action_pressed(Action::Confirm))
+        });
+        c.frame(|c| assert!(!c.is_key_down(Key::Enter)));
+    }
+
+    #[test]
+    fn drag_and_double_click() {
+        let mut c = HeadlessContext::new();
+        c.press(Button::MouseLeft);
+        c.frame(|c| assert_eq!(1, c.click_count(Button::MouseLeft)));
+        c.press(Button::MouseLeft);
+        c.frame(|c| assert_eq!(2, c.click_count(Button::MouseLeft)));
+
+        c.hold(Button::MouseRight);
+        c.frame(|c| assert!(c.is_down(Button::MouseRight)));
+        c.set_mouse(FPos { x: 50., y: 0. });
+        c.release(Button::MouseRight);
+        c.frame(|c| {
+            assert!(c.is_released(Button::MouseRight));
+            assert_eq!(Some(FPos { x: 50., y: 0. }), c.drag_delta(Button::MouseRight));
+            // the drag did not count as cancel click
+            assert!(!c.is_action_pressed(Action::Cancel));
+        });
+        c.press(Button::MouseRight);
+        c.frame(|c| assert!(c.is_action_pressed(Action::Cancel)));
+    }
+
+    #[test]
+    fn unknown_texture_draws_error() {
+        let mut c = HeadlessContext::new();
+        c.draw_texture("missing", 1., 2., 0);
+        assert_eq!(
+            DrawCall::Text {
+                text: "ERROR('missing')".into(),
+                size: 20.,
+                x: 1.,
+                y: 2.,
+                color: Color::DARKGRAY
+            },
+            c.draws[0].call
+        );
+    }
+
+    #[test]
+    fn styled_text() {
+        let mut c = HeadlessContext::new();
+        let style = TextStyle::new(10.).shadow(Color::BLACK).outline(Color::WHITE);
+        let style = style.align(HAlign::Center, VAlign::Top).wrap(30.);
+        let rect = c.draw_text("ab cd", &style, 50., 0., 1);
+        assert_eq!(Rect::new(42., 0., 16., 25.), rect);
+        assert_eq!(Rect::new(-8., 0., 16., 25.), c.measure_text("ab cd", &style));
+        let lines: Vec<String> = c.draws.iter().map(|d| d.call.to_string()).collect();
+        let expected = vec![
+            "text \"ab\" 10 rgba(0 0 0 1) at (42.833332 10.833333)",
+            "text \"ab\" 10 rgba(1 1 1 1) at (42 10)",
+            "text \"ab\" 10 rgba(0.31 0.31 0.31 1) at (42 10)",
+            "text \"cd\" 10 rgba(0 0 0 1) at (42.833332 25.833334)",
+            "text \"cd\" 10 rgba(1 1 1 1) at (42 25)",
+            "text \"cd\" 10 rgba(0.31 0.31 0.31 1) at (42 25)",
+        ];
+        assert_eq!(expected, lines);
+    }
+
+    #[test]
+    fn music_is_recorded_once() {
+        let mut c = HeadlessContext::new();
+        c.run_frames(3, |c| c.play_music("theme"));
+        c.play_sound("step");
+        c.stop_music();
+        c.stop_music();
+        c.set_volume(AudioChannel::Music, 2.);
+        let expected = vec![
+            AudioCall::Music("theme".into()),
+            AudioCall::Sound("step".into()),
+            AudioCall::StopMusic,
+        ];
+        assert_eq!(expected, c.audio);
+        assert_eq!(vec!["step"], c.sounds_played());
+        assert_eq!(1., c.volume(AudioChannel::Music));
+    }
+
+    #[test]
+    fn texture_status() {
+        let mut c = HeadlessContext::new();
+        c.load_texture("tiles", "tiles.png");
+        c.load_texture("broken", "broken.png");
+        assert_eq!(TextureStatus::Pending, c.texture_status("tiles"));
+        assert_eq!(TextureStatus::Missing, c.texture_status("other"));
+        assert_eq!(None, c.texture_dimensions("tiles"));
+
+        c.add_texture("tiles", 192., 64.);
+        c.failed.push("broken".into());
+        assert_eq!(TextureStatus::Loaded, c.texture_status("tiles"));
+        assert_eq!(TextureStatus::Failed, c.texture_status("broken"));
+        assert_eq!(Some(Rect::wh(192., 64.)), c.texture_dimensions("tiles"));
+    }
+
+    #[test]
+    fn camera_stays_in_bounds() {
+        let mut c = HeadlessContext::new();
+        c.view = Rect::wh(100., 50.);
+        c.set_camera_bounds(Some(Rect::wh(300., 200.)));
+        c.move_camera_to(FPos { x: 280., y: 100. }, 0.5);
+        assert_eq!(Rect::new(200., 75., 100., 50.), c.view);
+
+        // zooming out keeps the center until the view is bigger than the bounds
+        c.zoom_camera_to(1, 0.);
+        assert_eq!(Rect::new(100., 50., 200., 100.), c.view);
+        c.zoom_camera_to(0, 0.);
+        assert_eq!((1, Rect::new(100., 50., 200., 100.)), (c.zoom, c.view));
+
+        c.follow_camera(Some(FPos { x: 290., y: 100. }));
+        assert_eq!(FPos { x: 200., y: 100. }, c.view.center());
+    }
+
+    #[test]
+    fn same_z_draws_are_batched() {
+        let mut c = HeadlessContext::new();
+        c.add_texture("tiles", 16., 16.);
+        c.add_texture("ui_bg", 16., 16.);
+        c.draw_text("a", &TextStyle::new(6.), 0., 0., 1);
+        c.draw_texture("ui_bg", 0., 0., 1);
+        c.draw_rect(Rect::wh(1., 1.), Color::WHITE, 1);
+        c.draw_texture("tiles", 0., 0., 1);
+        c.draw_texture("ui_bg", 1., 1., 1);
+        let batches: Vec<usize> = c.draws_sorted().iter().map(|d| d.batch).collect();
+        assert_eq!(vec![SHAPES, 1, 2, 2, OWN_TEXTURE], batches);
+    }
+
+    #[test]
+    fn snapshot_format() {
+        let mut c = HeadlessContext::new();
+        c.add_texture("tiles", 16., 16.);
+        c.draw_texture_part("tiles", Rect::new(16., 0., 16., 16.), 32., 48., 10);
+        c.draw_text("Hi", &TextStyle::new(6.), 1., 2., 150);
+        c.draw_rect(Rect::new(0., 0., 4., 4.5), Color::rgb(1., 0.5, 0.), 1);
+        let params = DrawParams::new().flip(true, false).scale(2.);
+        c.draw_texture_ex("tiles", Rect::wh(16., 16.), 0., 0., &params, 20);
+        // screen space is drawn last regardless of the z level
+        c.set_space(Space::Screen);
+        c.draw_rect(Rect::wh(2., 2.), Color::WHITE, 0);
+        let expected = "\
+z=1 rect (0 0 4 4.5) rgba(1 0.5 0 1)
+z=10 texture tiles (16 0 16 16) at (32 48)
+z=20 texture tiles (0 0 16 16) at (0 0) flip (true false) scale 2
+z=150 text \"Hi\" 6 rgba(0.31 0.31 0.31 1) at (1 2)
+z=0 screen rect (0 0 2 2) rgba(1 1 1 1)
+";
+        assert_eq!(expected, c.snapshot());
+    }
+
+    #[test]
+    fn snapshot_mismatch_panics() {
+        let path = std::env::temp_dir().join("base_headless_snapshot_mismatch.snap");
+        std::fs::write(&path, "z=1 a\nz=2 b\n").unwrap();
+        assert_snapshot(&path, "z=1 a\nz=2 b\n");
+        let result = std::panic::catch_unwind(|| assert_snapshot(&path, "z=1 a\nz=2 c\n"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//! keyboard input and logical actions on top of it
+//! there are no gamepad bindings, macroquad can't read gamepads
+
+use nanoserde::{DeJson, SerJson};
+
+use crate::{Button, FPos};
+
+/// keyboard keys, named after their macroquad counterparts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SerJson, DeJson)]
+pub enum Key {
+    Space,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Right,
+    Left,
+    Down,
+    Up,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    LeftShift,
+    LeftControl,
+    LeftAlt,
+    RightShift,
+    RightControl,
+    RightAlt,
+}
+
+impl Key {
+    pub const ALL: [Key; 64] = [
+        Key::Space,
+        Key::Key0,
+        Key::Key1,
+        Key::Key2,
+        Key::Key3,
+        Key::Key4,
+        Key::Key5,
+        Key::Key6,
+        Key::Key7,
+        Key::Key8,
+        Key::Key9,
+        Key::A,
+        Key::B,
+        Key::C,
+        Key::D,
+        Key::E,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::I,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::M,
+        Key::N,
+        Key::O,
+        Key::P,
+        Key::Q,
+        Key::R,
+        Key::S,
+        Key::T,
+        Key::U,
+        Key::V,
+        Key::W,
+        Key::X,
+        Key::Y,
+        Key::Z,
+        Key::Escape,
+        Key::Enter,
+        Key::Tab,
+        Key::Backspace,
+        Key::Delete,
+        Key::Right,
+        Key::Left,
+        Key::Down,
+        Key::Up,
+        Key::F1,
+        Key::F2,
+        Key::F3,
+        Key::F4,
+        Key::F5,
+        Key::F6,
+        Key::F7,
+        Key::F8,
+        Key::F9,
+        Key::F10,
+        Key::F11,
+        Key::F12,
+        Key::LeftShift,
+        Key::LeftControl,
+        Key::LeftAlt,
+        Key::RightShift,
+        Key::RightControl,
+        Key::RightAlt,
+    ];
+}
+
+/// what the player wants to do, independent of the device doing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SerJson, DeJson)]
+pub enum Action {
+    Confirm,
+    Cancel,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    EndTurn,
+    NextUnit,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::Confirm,
+        Action::Cancel,
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::EndTurn,
+        Action::NextUnit,
+    ];
+
+    /// name shown to the player
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Confirm => "Confirm",
+            Action::Cancel => "Cancel",
+            Action::CursorUp => "Cursor up",
+            Action::CursorDown => "Cursor down",
+            Action::CursorLeft => "Cursor left",
+            Action::CursorRight => "Cursor right",
+            Action::EndTurn => "End turn",
+            Action::NextUnit => "Next unit",
+        }
+    }
+
+    /// keys triggering the action unless rebound
+    pub fn default_keys(self) -> &'static [Key] {
+        match self {
+            Action::Confirm => &[Key::Enter, Key::Space],
+            Action::Cancel => &[Key::Escape, Key::Backspace],
+            Action::CursorUp => &[Key::Up, Key::W],
+            Action::CursorDown => &[Key::Down, Key::S],
+            Action::CursorLeft => &[Key::Left, Key::A],
+            Action::CursorRight => &[Key::Right, Key::D],
+            Action::EndTurn => &[Key::E],
+            Action::NextUnit => &[Key::Tab],
+        }
+    }
+
+    /// mouse buttons triggering the action unless rebound
+    /// they trigger on click instead of press, so dragging with them does not fire the action
+    /// confirming with the left mouse button is position dependent, so it is handled by the game
+    pub fn default_buttons(self) -> &'static [Button] {
+        match self {
+            Action::Cancel => &[Button::MouseRight],
+            _ => &[],
+        }
+    }
+}
+
+/// a single key or mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(Key),
+    Button(Button),
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::Button(button) => write!(f, "{button:?}"),
+        }
+    }
+}
+
+/// inputs bound to a single action
+#[derive(Debug, Clone, PartialEq, SerJson, DeJson)]
+pub struct ActionBindings {
+    pub action: Action,
+    pub keys: Vec<Key>,
+    pub buttons: Vec<Button>,
+}
+
+impl ActionBindings {
+    pub fn contains(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.contains(&key),
+            Binding::Button(button) => self.buttons.contains(&button),
+        }
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = Binding> + '_ {
+        let keys = self.keys.iter().map(|key| Binding::Key(*key));
+        keys.chain(self.buttons.iter().map(|button| Binding::Button(*button)))
+    }
+}
+
+/// maps keys and mouse buttons to actions, stored in a config file so players can rebind
+#[derive(Debug, Clone, PartialEq, SerJson, DeJson)]
+pub struct InputMap {
+    pub bindings: Vec<ActionBindings>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|action| ActionBindings {
+                action: *action,
+                keys: action.default_keys().to_vec(),
+                buttons: action.default_buttons().to_vec(),
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl InputMap {
+    /// reads the map from a json file, falls back to the defaults if that fails
+    /// actions missing in the file get their default bindings
+    pub fn load(path: &str) -> Self {
+        let loaded: Option<InputMap> = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|input| DeJson::deserialize_json(&input).ok());
+        let mut map = Self::default();
+        if let Some(loaded) = loaded {
+            for bindings in loaded.bindings {
+                map.set(bindings);
+            }
+        }
+        map
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.serialize_json())
+    }
+
+    pub fn get(&self, action: Action) -> Option<&ActionBindings> {
+        self.bindings.iter().find(|b| b.action == action)
+    }
+
+    fn set(&mut self, bindings: ActionBindings) {
+        match self.bindings.iter_mut().find(|b| b.action == bindings.action) {
+            Some(existing) => *existing = bindings,
+            None => self.bindings.push(bindings),
+        }
+    }
+
+    /// replaces the bindings of the same kind for this action,
+    /// binding a key keeps the mouse buttons and vice versa
+    ///
+    /// returns the other actions that are bound to the same input
+    pub fn bind(&mut self, action: Action, binding: Binding) -> Vec<Action> {
+        let mut bindings = self.get(action).cloned().unwrap_or(ActionBindings {
+            action,
+            keys: Vec::new(),
+            buttons: Vec::new(),
+        });
+        match binding {
+            Binding::Key(key) => bindings.keys = vec![key],
+            Binding::Button(button) => bindings.buttons = vec![button],
+        }
+        self.set(bindings);
+        self.actions_for(binding).into_iter().filter(|a| *a != action).collect()
+    }
+
+    /// all actions triggered by the input
+    pub fn actions_for(&self, binding: Binding) -> Vec<Action> {
+        self.bindings.iter().filter(|b| b.contains(binding)).map(|b| b.action).collect()
+    }
+
+    /// inputs which are bound to more than one action
+    pub fn conflicts(&self) -> Vec<(Binding, Vec<Action>)> {
+        let mut result: Vec<(Binding, Vec<Action>)> = Vec::new();
+        for binding in self.bindings.iter().flat_map(|b| b.bindings()) {
+            let actions = self.actions_for(binding);
+            if actions.len() > 1 && !result.iter().any(|(b, _)| *b == binding) {
+                result.push((binding, actions));
+            }
+        }
+        result
+    }
+
+    /// true if any key or button bound to the action satisfies the predicates
+    pub fn is_active<K, B>(&self, action: Action, key: K, button: B) -> bool
+    where
+        K: Fn(Key) -> bool,
+        B: Fn(Button) -> bool,
+    {
+        self.get(action).is_some_and(|b| {
+            b.keys.iter().any(|k| key(*k)) || b.buttons.iter().any(|bt| button(*bt))
+        })
+    }
+}
+
+/// tracks drags and repeated clicks of a single mouse button
+#[derive(Debug, Clone, Default)]
+pub struct ClickTracker {
+    /// screen position where the button went down, kept during the release frame
+    down_at: Option<FPos>,
+    released: bool,
+    pos: FPos,
+    last_press: Option<(f64, FPos)>,
+    clicks: u32,
+}
+
+impl ClickTracker {
+    /// max seconds between presses counting as a double click
+    pub const MULTI_CLICK_TIME: f64 = 0.3;
+    /// max distance in screen space units before a press counts as drag instead of click
+    pub const CLICK_DISTANCE: f32 = 4.;
+
+    /// call once per frame before the game reads input
+    pub fn update(&mut self, time: f64, pos: FPos, pressed: bool, released: bool) {
+        if self.released {
+            self.down_at = None;
+            self.released = false;
+        }
+        self.pos = pos;
+        if pressed {
+            let repeated = self.last_press.is_some_and(|(t, p)| {
+                time - t <= Self::MULTI_CLICK_TIME && distance(p, pos) <= Self::CLICK_DISTANCE
+            });
+            self.clicks = if repeated { self.clicks + 1 } else { 1 };
+            self.last_press = Some((time, pos));
+            self.down_at = Some(pos);
+        }
+        if released {
+            self.released = true;
+        }
+    }
+
+    pub fn drag_delta(&self) -> Option<FPos> {
+        self.down_at.map(|start| FPos { x: self.pos.x - start.x, y: self.pos.y - start.y })
+    }
+
+    pub fn click_count(&self) -> u32 {
+        self.clicks
+    }
+
+    /// released this frame without being dragged
+    pub fn is_click(&self) -> bool {
+        let dragged = self
+            .drag_delta()
+            .is_some_and(|d| distance(FPos { x: 0., y: 0. }, d) > Self::CLICK_DISTANCE);
+        self.released && !dragged
+    }
+}
+
+fn distance(a: FPos, b: FPos) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebind_and_conflicts() {
+        let mut map = InputMap::default();
+        assert!(map.conflicts().is_empty());
+
+        // binding a key keeps the mouse buttons
+        let others = map.bind(Action::Cancel, Binding::Key(Key::E));
+        assert_eq!(vec![Action::EndTurn], others);
+        let cancel = map.get(Action::Cancel).unwrap();
+        assert_eq!(vec![Key::E], cancel.keys);
+        assert_eq!(vec![Button::MouseRight], cancel.buttons);
+
+        let conflicts = map.conflicts();
+        assert_eq!(1, conflicts.len());
+        assert_eq!(
+            (Binding::Key(Key::E), vec![Action::Cancel, Action::EndTurn]),
+            conflicts[0]
+        );
+
+        map.bind(Action::EndTurn, Binding::Key(Key::Q));
+        assert!(map.conflicts().is_empty());
+        assert!(map.is_active(Action::EndTurn, |k| k == Key::Q, |_| false));
+        assert!(!map.is_active(Action::EndTurn, |k| k == Key::E, |_| false));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join("base_input_map_test.json");
+        let path = path.to_str().unwrap();
+        let mut map = InputMap::default();
+        map.bind(Action::NextUnit, Binding::Button(Button::MouseMiddle));
+        map.save(path).unwrap();
+        assert_eq!(map, InputMap::load(path));
+
+        // missing actions are filled with the defaults
+        std::fs::write(
+            path,
+            r#"{"bindings":[{"action":"Confirm","keys":["X"],"buttons":[]}]}"#,
+        )
+        .unwrap();
+        let loaded = InputMap::load(path);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(vec![Key::X], loaded.get(Action::Confirm).unwrap().keys);
+        assert_eq!(Action::ALL.len(), loaded.bindings.len());
+
+        assert_eq!(InputMap::default(), InputMap::load("/does/not/exist.json"));
+    }
+
+    #[test]
+    fn click_tracking() {
+        let origin = FPos { x: 10., y: 10. };
+        let mut t = ClickTracker::default();
+        assert_eq!(None, t.drag_delta());
+
+        // double click
+        t.update(0.0, origin, true, true);
+        assert_eq!(1, t.click_count());
+        assert!(t.is_click());
+        t.update(0.1, origin, false, false);
+        assert_eq!(None, t.drag_delta());
+        t.update(0.2, origin, true, true);
+        assert_eq!(2, t.click_count());
+
+        // too slow for a triple click
+        t.update(1.0, origin, true, false);
+        assert_eq!(1, t.click_count());
+
+        // drag while held, the delta is still there when releasing
+        t.update(1.1, FPos { x: 30., y: 15. }, false, false);
+        assert_eq!(Some(FPos { x: 20., y: 5. }), t.drag_delta());
+        t.update(1.2, FPos { x: 40., y: 10. }, false, true);
+        assert_eq!(Some(FPos { x: 30., y: 0. }), t.drag_delta());
+        assert!(!t.is_click());
+        t.update(1.3, FPos { x: 40., y: 10. }, false, false);
+        assert_eq!(None, t.drag_delta());
+    }
+}
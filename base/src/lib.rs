@@ -1,8 +1,16 @@
 use std::{ffi::c_void, ops::Sub};
+
+use input::{Action, Binding, InputMap, Key};
+use nanoserde::{DeJson, SerJson};
+use text::TextStyle;
 pub mod circle;
 pub mod grids;
+pub mod headless;
+pub mod input;
 pub mod ldtk;
+pub mod nine_patch;
 pub mod rect;
+pub mod text;
 
 pub trait ContextTrait {
     /// time since program start
@@ -20,7 +28,18 @@ pub trait ContextTrait {
 
     fn draw_circle(&mut self, circle: Circle, c: Color, z_level: i32);
 
-    fn draw_text(&mut self, text: &str, size: f32, x: f32, y: f32, z_level: i32) -> Rect;
+    /// x and y are anchored according to the style's alignment, returns the covered rect
+    fn draw_text(
+        &mut self,
+        text: &str,
+        style: &TextStyle,
+        x: f32,
+        y: f32,
+        z_level: i32,
+    ) -> Rect;
+
+    /// the rect draw_text would cover at 0,0 without drawing anything
+    fn measure_text(&self, text: &str, style: &TextStyle) -> Rect;
 
     fn draw_texture(&mut self, name: &str, x: f32, y: f32, z_level: i32);
 
@@ -28,24 +47,237 @@ pub trait ContextTrait {
 
     fn draw_texture_part_scaled(&mut self, name: &str, src: Rect, target: Rect, z_level: i32);
 
+    /// draw_texture_part with tint, flipping, rotation and scale
+    fn draw_texture_ex(
+        &mut self,
+        name: &str,
+        src: Rect,
+        x: f32,
+        y: f32,
+        params: &DrawParams,
+        z_level: i32,
+    );
+
+    /// tiles of a texture that rarely change, like a map layer
+    /// the context can bake them once and redraw them in a single call,
+    /// `layer` identifies the baked result, it is baked again when the tiles change
+    fn draw_tile_layer(&mut self, layer: &str, texture: &str, tiles: &[Tile], z_level: i32);
+
+    /// queues a texture for loading, see [ContextTrait::texture_status]
     fn load_texture(&mut self, name: &str, path: &str);
 
-    fn texture_dimensions(&mut self, name: &str) -> Rect;
+    /// loads the texture again from the path it was loaded from,
+    /// draws keep using the old version until the new one is loaded
+    fn reload_texture(&mut self, name: &str);
+
+    /// size of a loaded texture at 0,0
+    fn texture_dimensions(&self, name: &str) -> Option<Rect>;
+
+    fn texture_status(&self, name: &str) -> TextureStatus;
+
+    /// queues a sound for loading, it can be played by name once loaded
+    fn load_sound(&mut self, name: &str, path: &str);
+
+    /// plays a sound effect once, missing sounds are ignored
+    /// the app only outputs sound when built with its `audio` feature
+    fn play_sound(&mut self, name: &str);
+
+    /// loops a sound as music and stops the previous track,
+    /// keeps playing if the track is already running,
+    /// a track that is still loading starts once it is loaded
+    fn play_music(&mut self, name: &str);
 
+    fn stop_music(&mut self);
+
+    /// volume between 0 and 1
+    fn set_volume(&mut self, channel: AudioChannel, volume: f32);
+
+    fn volume(&self, channel: AudioChannel) -> f32;
+
+    /// button went down this frame
     fn is_pressed(&self, button: Button) -> bool;
 
+    /// button is held down
+    fn is_down(&self, button: Button) -> bool;
+
+    /// button went up this frame
+    fn is_released(&self, button: Button) -> bool;
+
+    /// screen space distance the mouse moved since the button went down,
+    /// available while the button is down and in the frame it is released
+    fn drag_delta(&self, button: Button) -> Option<FPos>;
+
+    /// 1 for a single click, 2 for a double click and so on
+    /// refers to the latest press of the button
+    fn click_count(&self, button: Button) -> u32;
+
+    /// key went down this frame
+    fn is_key_pressed(&self, key: Key) -> bool;
+
+    /// key is held down
+    fn is_key_down(&self, key: Key) -> bool;
+
+    /// key went up this frame
+    fn is_key_released(&self, key: Key) -> bool;
+
+    /// any input bound to the action was pressed this frame
+    fn is_action_pressed(&self, action: Action) -> bool;
+
+    /// the first key or mouse button pressed this frame, used to capture new bindings
+    fn last_input(&self) -> Option<Binding>;
+
+    /// current bindings of all actions
+    fn input_map(&self) -> InputMap;
+
+    /// replaces the bindings and persists them
+    fn set_input_map(&mut self, map: InputMap);
+
+    /// mouse position in screen space units, the ones [Space::Screen] draws and
+    /// [ContextTrait::screen_rect] use, not window pixels
     fn mouse_screen(&self) -> FPos;
 
     fn mouse_world(&self) -> FPos;
+
+    /// world space area covered by the camera, draws outside of it are skipped anyway
+    fn visible_world_rect(&self) -> Rect;
+
+    /// pans until pos is in the middle of the screen, at once for a duration of 0
+    fn move_camera_to(&mut self, pos: FPos, duration: f32);
+
+    /// world units are 2^zoom pixels big, the player can zoom between 1 and 5
+    fn zoom_camera_to(&mut self, zoom: i32, duration: f32);
+
+    /// eases the camera towards the target every frame until it is None again
+    /// meant to be called every frame with the position of something moving
+    fn follow_camera(&mut self, target: Option<FPos>);
+
+    /// moves the view by up to `strength` world units, fading out over the duration
+    fn shake_camera(&mut self, strength: f32, duration: f32);
+
+    /// keeps the view inside of the bounds, centered on them if it is bigger
+    fn set_camera_bounds(&mut self, bounds: Option<Rect>);
+
+    /// draws until the next call use the given space, every frame starts in world space
+    fn set_space(&mut self, space: Space);
+
+    /// screen space area, a unit is as big as a world unit at the default zoom
+    fn screen_rect(&self) -> Rect;
+}
+
+/// load state of a texture, loading happens between frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureStatus {
+    /// never loaded or queued
+    Missing,
+    Pending,
+    Loaded,
+    Failed,
+}
+
+/// coordinate space of draw calls
+/// screen space ignores the camera and is drawn above the whole world, for menus and hud
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Space {
+    #[default]
+    World,
+    Screen,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SerJson, DeJson)]
 pub enum Button {
     MouseLeft,
     MouseMiddle,
     MouseRight,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Button {
+    pub const ALL: [Button; 3] = [Button::MouseLeft, Button::MouseMiddle, Button::MouseRight];
+
+    /// position in [Button::ALL]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// optional parameters of [ContextTrait::draw_texture_ex]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawParams {
+    /// multiplied with the texture color, white keeps it unchanged
+    pub tint: Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// clockwise in radians
+    pub rotation: f32,
+    /// rotation center relative to the top left corner, the center if None
+    pub pivot: Option<FPos>,
+    /// drawn at this size instead of the size of the source rect, stretching it
+    pub size: Option<FPos>,
+    /// multiplies the drawn size
+    pub scale: f32,
+}
+
+impl DrawParams {
+    pub const fn new() -> Self {
+        DrawParams {
+            tint: Color::WHITE,
+            flip_x: false,
+            flip_y: false,
+            rotation: 0.,
+            pivot: None,
+            size: None,
+            scale: 1.,
+        }
+    }
+
+    pub const fn tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub const fn flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+
+    pub const fn rotate(mut self, rotation: f32, pivot: Option<FPos>) -> Self {
+        self.rotation = rotation;
+        self.pivot = pivot;
+        self
+    }
+
+    pub const fn size(mut self, w: f32, h: f32) -> Self {
+        self.size = Some(FPos { x: w, y: h });
+        self
+    }
+
+    pub const fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+impl Default for DrawParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// part of a texture drawn at a world position, see [ContextTrait::draw_tile_layer]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub source_rect: Rect,
+    pub pos: FPos,
+}
+
+/// sound effects and music have separate volumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioChannel {
+    Sfx,
+    Music,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct FPos {
     pub x: f32,
     pub y: f32,
@@ -69,6 +301,16 @@ impl Pos {
     pub fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    pub fn manhattan(self, other: Pos) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+}
+
+impl From<(i32, i32)> for Pos {
+    fn from((x, y): (i32, i32)) -> Self {
+        Self { x, y }
+    }
 }
 
 impl Sub<Pos> for Pos {
@@ -96,7 +338,7 @@ impl PersistWrapper {
 }
 
 /// x and y are in the top left
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -104,7 +346,7 @@ pub struct Rect {
     pub h: f32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -112,17 +354,22 @@ pub struct Color {
     pub a: f32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Circle {
     pub pos: FPos,
     pub radius: f32,
 }
 
 impl Color {
-    pub fn rgb(r: f32, g: f32, b: f32) -> Self {
+    pub const WHITE: Color = Color::rgb(1., 1., 1.);
+    pub const BLACK: Color = Color::rgb(0., 0., 0.);
+    pub const DARKGRAY: Color = Color::rgb(0.31, 0.31, 0.31);
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
         Color { r, g, b, a: 1.0 }
     }
 
-    pub fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
         Color { r, g, b, a }
     }
 }
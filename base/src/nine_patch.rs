@@ -0,0 +1,195 @@
+use crate::{Color, ContextTrait, DrawParams, Rect};
+
+/// distances from the edges of a rect
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    pub const fn all(amount: f32) -> Self {
+        Insets { left: amount, top: amount, right: amount, bottom: amount }
+    }
+}
+
+/// how the edges and the center fill the space between the corners
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeMode {
+    #[default]
+    Stretch,
+    /// repeated at their original size, the last repetition is cut off
+    Tile,
+}
+
+/// a texture split into a 3x3 grid by insets, corners keep their size when drawn
+/// while the edges and the center grow with the target rect
+#[derive(Debug, Clone, PartialEq)]
+pub struct NinePatch {
+    pub texture: String,
+    /// part of the texture holding the patch, the whole texture if None
+    pub source: Option<Rect>,
+    /// the same insets are used for source and target
+    pub insets: Insets,
+    pub edges: EdgeMode,
+    pub tint: Color,
+}
+
+impl NinePatch {
+    pub fn new(texture: &str, insets: Insets) -> Self {
+        NinePatch {
+            texture: texture.to_string(),
+            source: None,
+            insets,
+            edges: EdgeMode::Stretch,
+            tint: Color::WHITE,
+        }
+    }
+
+    pub fn source(mut self, source: Rect) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn edges(mut self, edges: EdgeMode) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    pub fn tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// draws nothing without a source rect until the texture is loaded
+    pub fn draw(&self, c: &mut dyn ContextTrait, target: Rect, z_level: i32) {
+        let Some(source) = self.source.or_else(|| c.texture_dimensions(&self.texture)) else {
+            return;
+        };
+        let src_cols = split(source.x, source.w, self.insets.left, self.insets.right);
+        let src_rows = split(source.y, source.h, self.insets.top, self.insets.bottom);
+        let cols = split(target.x, target.w, self.insets.left, self.insets.right);
+        let rows = split(target.y, target.h, self.insets.top, self.insets.bottom);
+
+        for (j, (row, src_row)) in rows.iter().zip(src_rows).enumerate() {
+            for (i, (col, src_col)) in cols.iter().zip(src_cols).enumerate() {
+                let src = Rect::new(src_col.0, src_row.0, src_col.1, src_row.1);
+                let target = Rect::new(col.0, row.0, col.1, row.1);
+                let tile = self.edges == EdgeMode::Tile;
+                self.draw_part(c, src, target, tile && i == 1, tile && j == 1, z_level);
+            }
+        }
+    }
+
+    /// stretches src over target, or repeats it along the tiled axes
+    fn draw_part(
+        &self,
+        c: &mut dyn ContextTrait,
+        src: Rect,
+        target: Rect,
+        tile_x: bool,
+        tile_y: bool,
+        z_level: i32,
+    ) {
+        if src.w <= 0. || src.h <= 0. || target.w <= 0. || target.h <= 0. {
+            return;
+        }
+        let step_x = if tile_x { src.w } else { target.w };
+        let step_y = if tile_y { src.h } else { target.h };
+        let mut y = target.y;
+        while y < target.y + target.h {
+            let h = step_y.min(target.y + target.h - y);
+            let mut x = target.x;
+            while x < target.x + target.w {
+                let w = step_x.min(target.x + target.w - x);
+                // cut off repetitions show the start of the source
+                let piece = Rect {
+                    w: if tile_x { w } else { src.w },
+                    h: if tile_y { h } else { src.h },
+                    ..src
+                };
+                let params = DrawParams::new().tint(self.tint).size(w, h);
+                c.draw_texture_ex(&self.texture, piece, x, y, &params, z_level);
+                x += step_x;
+            }
+            y += step_y;
+        }
+    }
+}
+
+/// (start, length) of the three parts of a span
+fn split(start: f32, len: f32, before: f32, after: f32) -> [(f32, f32); 3] {
+    let middle = (len - before - after).max(0.);
+    [(start, before), (start + before, middle), (start + before + middle, after)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::{DrawCall, HeadlessContext};
+
+    fn parts(c: &HeadlessContext) -> Vec<(Rect, Rect)> {
+        c.draws
+            .iter()
+            .map(|d| match &d.call {
+                DrawCall::TexturePart { src, x, y, params, .. } => {
+                    let size = params.size.unwrap();
+                    (*src, Rect::new(*x, *y, size.x, size.y))
+                }
+                call => panic!("unexpected {call}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nine_parts() {
+        let mut c = HeadlessContext::new();
+        c.add_texture("ui_bg", 30., 20.);
+        let tint = Color::rgb(1., 0., 0.);
+        let patch =
+            NinePatch::new("ui_bg", Insets { left: 2., top: 3., right: 4., bottom: 5. })
+                .tint(tint);
+        c.frame(|c| patch.draw(c, Rect::new(100., 200., 50., 40.), 7));
+
+        #[rustfmt::skip]
+        let expected = vec![
+            (Rect::new(0., 0., 2., 3.), Rect::new(100., 200., 2., 3.)),
+            (Rect::new(2., 0., 24., 3.), Rect::new(102., 200., 44., 3.)),
+            (Rect::new(26., 0., 4., 3.), Rect::new(146., 200., 4., 3.)),
+            (Rect::new(0., 3., 2., 12.), Rect::new(100., 203., 2., 32.)),
+            (Rect::new(2., 3., 24., 12.), Rect::new(102., 203., 44., 32.)),
+            (Rect::new(26., 3., 4., 12.), Rect::new(146., 203., 4., 32.)),
+            (Rect::new(0., 15., 2., 5.), Rect::new(100., 235., 2., 5.)),
+            (Rect::new(2., 15., 24., 5.), Rect::new(102., 235., 44., 5.)),
+            (Rect::new(26., 15., 4., 5.), Rect::new(146., 235., 4., 5.)),
+        ];
+        assert_eq!(expected, parts(&c));
+        assert!(c.draws.iter().all(|d| d.z_level == 7
+            && matches!(&d.call, DrawCall::TexturePart { params, .. } if params.tint == tint)));
+    }
+
+    #[test]
+    fn tiled_edges() {
+        let mut c = HeadlessContext::new();
+        c.add_texture("ui_bg", 12., 12.);
+        let patch = NinePatch::new("ui_bg", Insets::all(4.))
+            .source(Rect::new(0., 0., 12., 12.))
+            .edges(EdgeMode::Tile);
+        c.frame(|c| patch.draw(c, Rect::wh(18., 12.), 0));
+
+        // the middle column is 10 wide, two full repetitions and a cut off one
+        let top: Vec<(Rect, Rect)> =
+            parts(&c).into_iter().filter(|(_, t)| t.y == 0.).collect();
+        #[rustfmt::skip]
+        let expected = vec![
+            (Rect::new(0., 0., 4., 4.), Rect::new(0., 0., 4., 4.)),
+            (Rect::new(4., 0., 4., 4.), Rect::new(4., 0., 4., 4.)),
+            (Rect::new(4., 0., 4., 4.), Rect::new(8., 0., 4., 4.)),
+            (Rect::new(4., 0., 2., 4.), Rect::new(12., 0., 2., 4.)),
+            (Rect::new(8., 0., 4., 4.), Rect::new(14., 0., 4., 4.)),
+        ];
+        assert_eq!(expected, top);
+    }
+}
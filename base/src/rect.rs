@@ -91,7 +91,32 @@ impl Rect {
             && pos.y < self.y + self.h
     }
 
+    /// true if the rects share any area, touching edges don't count
+    pub fn overlaps(&self, other: Rect) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+
     pub fn center(&self) -> FPos {
         FPos { x: self.x + self.w / 2.0, y: self.y + self.h / 2.0 }
     }
+
+    /// moved as little as possible to lie inside of bounds, centered on them along axes
+    /// it is too big for
+    pub fn keep_inside(&self, bounds: Rect) -> Self {
+        let fit = |start: f32, len: f32, min: f32, space: f32| {
+            if len >= space {
+                min + (space - len) / 2.
+            } else {
+                start.clamp(min, min + space - len)
+            }
+        };
+        Rect {
+            x: fit(self.x, self.w, bounds.x, bounds.w),
+            y: fit(self.y, self.h, bounds.y, bounds.h),
+            ..*self
+        }
+    }
 }
@@ -0,0 +1,217 @@
+use crate::{Color, FPos, Rect};
+
+/// horizontal anchor of the x coordinate passed to draw_text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// vertical anchor of the y coordinate passed to draw_text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Middle,
+    /// baseline of the first line
+    Baseline,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub size: f32,
+    pub color: Color,
+    pub h_align: HAlign,
+    pub v_align: VAlign,
+    /// lines are wrapped at spaces to stay below this width, single words are never split
+    pub max_width: Option<f32>,
+    /// drawn around the glyphs, see [TextStyle::effect_offset]
+    pub outline: Option<Color>,
+    /// drawn below and to the right of the glyphs
+    pub shadow: Option<Color>,
+}
+
+impl TextStyle {
+    /// distance between the baselines of two lines relative to the size
+    pub const LINE_SPACING: f32 = 1.5;
+
+    /// dark gray, left aligned on the baseline like text in most ui toolkits
+    pub const fn new(size: f32) -> Self {
+        TextStyle {
+            size,
+            color: Color::DARKGRAY,
+            h_align: HAlign::Left,
+            v_align: VAlign::Baseline,
+            max_width: None,
+            outline: None,
+            shadow: None,
+        }
+    }
+
+    pub const fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub const fn align(mut self, h_align: HAlign, v_align: VAlign) -> Self {
+        self.h_align = h_align;
+        self.v_align = v_align;
+        self
+    }
+
+    pub const fn wrap(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub const fn outline(mut self, color: Color) -> Self {
+        self.outline = Some(color);
+        self
+    }
+
+    pub const fn shadow(mut self, color: Color) -> Self {
+        self.shadow = Some(color);
+        self
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.size * Self::LINE_SPACING
+    }
+
+    /// outline thickness and shadow distance
+    pub fn effect_offset(&self) -> f32 {
+        self.size / 12.
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(6.)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    /// start of the baseline
+    pub pos: FPos,
+    pub rect: Rect,
+}
+
+/// where the lines of a text end up, computed the same way by every context
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub lines: Vec<TextLine>,
+    /// covers all lines
+    pub rect: Rect,
+}
+
+impl TextLayout {
+    /// `measure` returns the rect of a single line drawn with its baseline starting at 0,0
+    pub fn new(
+        text: &str,
+        style: &TextStyle,
+        x: f32,
+        y: f32,
+        measure: impl Fn(&str) -> Rect,
+    ) -> Self {
+        let mut lines = Vec::new();
+        let mut rect: Option<Rect> = None;
+        for (i, line) in wrap(text, style.max_width, &measure).into_iter().enumerate() {
+            let m = measure(&line);
+            let dx = match style.h_align {
+                HAlign::Left => 0.,
+                HAlign::Center => -m.w / 2.,
+                HAlign::Right => -m.w,
+            };
+            let baseline = i as f32 * style.line_height();
+            let line_rect = Rect::new(dx + m.x, baseline + m.y, m.w, m.h);
+            rect = Some(rect.map_or(line_rect, |r| r.fuse(line_rect)));
+            lines.push(TextLine {
+                text: line,
+                pos: FPos { x: dx, y: baseline },
+                rect: line_rect,
+            });
+        }
+        let rect = rect.unwrap_or_default();
+
+        let dy = match style.v_align {
+            VAlign::Top => -rect.y,
+            VAlign::Middle => -(rect.y + rect.h / 2.),
+            VAlign::Baseline => 0.,
+            VAlign::Bottom => -(rect.y + rect.h),
+        };
+        let shift = |r: Rect| Rect { x: r.x + x, y: r.y + y + dy, ..r };
+        for line in lines.iter_mut() {
+            line.pos = FPos { x: line.pos.x + x, y: line.pos.y + y + dy };
+            line.rect = shift(line.rect);
+        }
+        TextLayout { lines, rect: shift(rect) }
+    }
+}
+
+/// splits at newlines and greedily at spaces when a max width is given
+fn wrap(text: &str, max_width: Option<f32>, measure: impl Fn(&str) -> Rect) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let Some(max_width) = max_width else {
+            lines.push(paragraph.to_string());
+            continue;
+        };
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate =
+                if line.is_empty() { word.to_string() } else { format!("{line} {word}") };
+            if !line.is_empty() && measure(&candidate).w > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// every char is 1 wide, ascent 2 and no descent
+    fn measure(text: &str) -> Rect {
+        Rect::new(0., -2., text.chars().count() as f32, 2.)
+    }
+
+    fn texts(layout: &TextLayout) -> Vec<&str> {
+        layout.lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        let style = TextStyle::new(2.).wrap(7.);
+        let layout =
+            TextLayout::new("one two three\nfour extraordinary", &style, 0., 0., measure);
+        assert_eq!(vec!["one two", "three", "four", "extraordinary"], texts(&layout));
+        assert_eq!(FPos { x: 0., y: 3. }, layout.lines[1].pos);
+        assert_eq!(Rect::new(0., -2., 13., 11.), layout.rect);
+    }
+
+    #[test]
+    fn alignment() {
+        let style = TextStyle::new(2.).align(HAlign::Center, VAlign::Top);
+        let layout = TextLayout::new("abcd\nab", &style, 10., 10., measure);
+        assert_eq!(Rect::new(8., 10., 4., 5.), layout.rect);
+        assert_eq!(FPos { x: 8., y: 12. }, layout.lines[0].pos);
+        assert_eq!(FPos { x: 9., y: 15. }, layout.lines[1].pos);
+
+        let style = TextStyle::new(2.).align(HAlign::Right, VAlign::Bottom);
+        let layout = TextLayout::new("abcd", &style, 10., 10., measure);
+        assert_eq!(Rect::new(6., 8., 4., 2.), layout.rect);
+        assert_eq!(FPos { x: 6., y: 10. }, layout.lines[0].pos);
+
+        let style = TextStyle::new(2.).align(HAlign::Left, VAlign::Middle);
+        let layout = TextLayout::new("abcd", &style, 10., 10., measure);
+        assert_eq!(Rect::new(10., 9., 4., 2.), layout.rect);
+    }
+}
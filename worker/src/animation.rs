@@ -0,0 +1,219 @@
+use std::{collections::HashMap, hash::Hash};
+
+use base::{ContextTrait, DrawParams, FPos};
+use nanoserde::DeJson;
+
+use crate::sprite::Sprite;
+
+#[derive(DeJson, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayMode {
+    #[default]
+    Loop,
+    /// holds the last frame when done
+    Once,
+}
+
+/// a sprite shown for a while, optionally moved, scaled around its center or faded
+#[derive(DeJson, Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub sprite: String,
+    /// seconds
+    pub duration: f32,
+    #[nserde(default)]
+    pub dx: f32,
+    #[nserde(default)]
+    pub dy: f32,
+    #[nserde(default = "1.")]
+    pub scale: f32,
+    #[nserde(default = "1.")]
+    pub alpha: f32,
+}
+
+#[derive(DeJson, Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+    #[nserde(default)]
+    pub mode: PlayMode,
+}
+
+impl Animation {
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+
+    pub fn is_finished(&self, elapsed: f64) -> bool {
+        self.mode == PlayMode::Once && elapsed >= self.duration() as f64
+    }
+
+    /// frame shown `elapsed` seconds after the start
+    pub fn frame_at(&self, elapsed: f64) -> &Frame {
+        let duration = self.duration() as f64;
+        let mut t = match self.mode {
+            PlayMode::Loop if duration > 0. => elapsed.rem_euclid(duration),
+            _ => elapsed,
+        };
+        for frame in &self.frames {
+            if t < frame.duration as f64 {
+                return frame;
+            }
+            t -= frame.duration as f64;
+        }
+        self.frames.last().expect("animations have at least one frame")
+    }
+
+    /// draws the frame at `elapsed` on top of the given params
+    pub fn draw(
+        &self,
+        c: &mut dyn ContextTrait,
+        sprites: &HashMap<String, Sprite>,
+        elapsed: f64,
+        pos: FPos,
+        params: &DrawParams,
+        z_level: i32,
+    ) {
+        let frame = self.frame_at(elapsed);
+        let Some(sprite) = sprites.get(&frame.sprite) else {
+            return;
+        };
+        let mut params = *params;
+        params.scale *= frame.scale;
+        params.tint.a *= frame.alpha;
+        // scaled around the center instead of the top left corner
+        let shift = (1. - frame.scale) / 2.;
+        let x = pos.x + frame.dx + sprite.src.w * shift;
+        let y = pos.y + frame.dy + sprite.src.h * shift;
+        sprite.draw_ex(c, x, y, &params, z_level);
+    }
+}
+
+pub fn load_animations(path: &str) -> Result<HashMap<String, Animation>, String> {
+    let input = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    parse_animations(&input).map_err(|e| format!("{path}: {e}"))
+}
+
+/// rejects animations that [Animation::frame_at] can't play
+fn parse_animations(input: &str) -> Result<HashMap<String, Animation>, String> {
+    let animations: HashMap<String, Animation> =
+        DeJson::deserialize_json(input).map_err(|e| e.to_string())?;
+    for (name, animation) in &animations {
+        if animation.frames.is_empty() {
+            return Err(format!("animation {name} has no frames"));
+        }
+        if animation.frames.iter().any(|f| f.duration <= 0.) {
+            return Err(format!("animation {name} has a frame without a duration"));
+        }
+    }
+    Ok(animations)
+}
+
+struct Playback {
+    name: String,
+    started: f64,
+}
+
+/// which animation every animated thing shows and since when, times come from
+/// [ContextTrait::time]
+pub struct AnimationPlayer<K> {
+    playing: HashMap<K, Playback>,
+}
+
+impl<K> Default for AnimationPlayer<K> {
+    fn default() -> Self {
+        Self { playing: HashMap::new() }
+    }
+}
+
+impl<K: Hash + Eq> AnimationPlayer<K> {
+    /// starts an animation from its first frame, a once animation plays until it is done
+    /// even if draw asks for another one, loops are replaced right away
+    pub fn play(&mut self, key: K, name: &str, time: f64) {
+        self.playing.insert(key, Playback { name: name.to_string(), started: time });
+    }
+
+    /// name and elapsed time of what `key` shows, `current` is the animation it should
+    /// show according to its state, it starts over when that changes
+    pub fn current(
+        &mut self,
+        key: K,
+        current: &str,
+        time: f64,
+        animations: &HashMap<String, Animation>,
+    ) -> (&str, f64) {
+        let playback = self
+            .playing
+            .entry(key)
+            .or_insert_with(|| Playback { name: current.to_string(), started: time });
+        let elapsed = time - playback.started;
+        let overlay = animations
+            .get(&playback.name)
+            .is_some_and(|a| a.mode == PlayMode::Once && !a.is_finished(elapsed));
+        if playback.name != current && !overlay {
+            *playback = Playback { name: current.to_string(), started: time };
+        }
+        (&playback.name, time - playback.started)
+    }
+
+    /// forgets things that are not drawn anymore
+    pub fn retain(&mut self, f: impl Fn(&K) -> bool) {
+        self.playing.retain(|key, _| f(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animations() -> HashMap<String, Animation> {
+        let json = r#"{
+            "idle": { "frames": [
+                { "sprite": "a", "duration": 0.5 },
+                { "sprite": "b", "duration": 0.25, "dy": -1, "scale": 2 }
+            ] },
+            "attack": { "mode": "Once", "frames": [
+                { "sprite": "c", "duration": 0.5, "alpha": 0.5 }
+            ] }
+        }"#;
+        parse_animations(json).unwrap()
+    }
+
+    #[test]
+    fn empty_or_instant_animations_are_rejected() {
+        let empty = r#"{ "idle": { "frames": [] } }"#;
+        assert_eq!(Err("animation idle has no frames".into()), parse_animations(empty));
+        let instant = r#"{ "idle": { "frames": [{ "sprite": "a", "duration": 0 }] } }"#;
+        assert_eq!(
+            Err("animation idle has a frame without a duration".into()),
+            parse_animations(instant)
+        );
+    }
+
+    #[test]
+    fn frames_loop_or_hold() {
+        let animations = animations();
+        let idle = &animations["idle"];
+        assert_eq!(PlayMode::Loop, idle.mode);
+        assert_eq!(("b", -1., 2., 1.), {
+            let f = idle.frame_at(0.6);
+            (f.sprite.as_str(), f.dy, f.scale, f.alpha)
+        });
+        assert_eq!("a", idle.frame_at(0.8).sprite);
+        assert!(!idle.is_finished(10.));
+
+        let attack = &animations["attack"];
+        assert_eq!("c", attack.frame_at(3.).sprite);
+        assert!(attack.is_finished(0.5));
+    }
+
+    #[test]
+    fn once_animations_finish_before_switching() {
+        let animations = animations();
+        let mut player = AnimationPlayer::default();
+        assert_eq!(("idle", 0.), player.current(1, "idle", 1., &animations));
+
+        player.play(1, "attack", 2.);
+        assert_eq!(("attack", 0.25), player.current(1, "idle", 2.25, &animations));
+        // back to the state animation, which starts over
+        assert_eq!(("idle", 0.), player.current(1, "idle", 2.5, &animations));
+        assert_eq!(("idle", 0.5), player.current(1, "idle", 3., &animations));
+    }
+}
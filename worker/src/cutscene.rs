@@ -0,0 +1,433 @@
+//! scripted sequences loaded from cutscenes.json, every one is played by a coroutine
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use base::{
+    text::{HAlign, TextStyle, VAlign},
+    ContextTrait, DrawParams, Pos, Rect, Space,
+};
+use cosync::CosyncInput;
+use nanoserde::DeJson;
+
+use crate::{
+    fleeting::FleetingState,
+    frame::FrameChannel,
+    game::{self, Actor, GameState, PLAYER_TEAM},
+    genarena::Key,
+    persistent::PersistentState,
+    ui,
+    util::game_to_world,
+    GRIDSIZE,
+};
+
+/// dialogue covers everything else on screen
+const Z_LEVEL: i32 = 500;
+const PORTRAIT_SCALE: f32 = 2.;
+const SPEAKER: TextStyle = ui::TEXT.color(base::Color::rgb(0.2, 0.3, 0.9));
+const SPEECH: TextStyle = TextStyle::new(6.).align(HAlign::Left, VAlign::Top);
+
+#[derive(DeJson, Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// the first frame of the level
+    LevelStart,
+    Turn {
+        turn: i32,
+    },
+    /// a unit of the player stands on the tile
+    Reach {
+        tile: [i32; 2],
+    },
+}
+
+impl Trigger {
+    fn is_met(&self, g: &GameState) -> bool {
+        match self {
+            Trigger::LevelStart => true,
+            Trigger::Turn { turn } => g.turn == *turn,
+            Trigger::Reach { tile } => {
+                g.actors.iter().any(|a| a.team == PLAYER_TEAM && a.pos == pos(*tile))
+            }
+        }
+    }
+}
+
+#[derive(DeJson, Debug, Clone, PartialEq)]
+pub enum Step {
+    /// shows a dialogue box until the player confirms
+    Say {
+        speaker: String,
+        portrait: String,
+        text: String,
+    },
+    /// scrolls the camera until the tile is in the middle of the screen
+    Pan {
+        tile: [i32; 2],
+        duration: f32,
+    },
+    /// zoom level like [ContextTrait::zoom_camera_to]
+    Zoom {
+        zoom: i32,
+        duration: f32,
+    },
+    Shake {
+        strength: f32,
+        duration: f32,
+    },
+    /// walks the unit on the first tile along the path, does nothing if the tile is empty
+    /// the camera follows it
+    Move {
+        path: Vec<[i32; 2]>,
+    },
+    /// plays an animation of the unit on the tile until it is done
+    Animate {
+        tile: [i32; 2],
+        animation: String,
+    },
+    Wait {
+        seconds: f32,
+    },
+    WaitConfirm,
+}
+
+#[derive(DeJson, Debug, Clone, PartialEq)]
+pub struct Cutscene {
+    pub trigger: Trigger,
+    pub steps: Vec<Step>,
+}
+
+pub fn load_cutscenes(path: &str) -> Vec<Cutscene> {
+    let input = std::fs::read_to_string(path).unwrap();
+    DeJson::deserialize_json(&input).unwrap()
+}
+
+fn pos([x, y]: [i32; 2]) -> Pos {
+    Pos::new(x, y)
+}
+
+/// a line of dialogue
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub speaker: String,
+    /// sprite from the tileset
+    pub portrait: String,
+    pub text: String,
+}
+
+#[derive(Default)]
+struct Shared {
+    /// cutscenes queued or running
+    playing: usize,
+    line: Option<Line>,
+    following: Option<Key<Actor>>,
+    /// area the camera stays in
+    bounds: Option<Rect>,
+    /// animations to start, the coroutines have no access to the player
+    animations: Vec<(Key<Actor>, String)>,
+}
+
+/// what the cutscene coroutines show, it lives in fleeting state like [FrameChannel]
+#[derive(Clone, Default)]
+pub struct Stage(Arc<Mutex<Shared>>);
+
+impl Stage {
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.0.lock().unwrap()
+    }
+
+    /// the game ignores the player while this is true
+    pub fn is_playing(&self) -> bool {
+        self.lock().playing > 0
+    }
+
+    pub fn line(&self) -> Option<Line> {
+        self.lock().line.clone()
+    }
+
+    /// unit the camera should keep in view
+    pub fn following(&self) -> Option<Key<Actor>> {
+        self.lock().following
+    }
+
+    /// kept here instead of in the context, so a reload that drops the cutscene frees the camera
+    pub fn camera_bounds(&self) -> Option<Rect> {
+        self.lock().bounds
+    }
+
+    /// animations started since the last call
+    pub fn take_animations(&self) -> Vec<(Key<Actor>, String)> {
+        std::mem::take(&mut self.lock().animations)
+    }
+
+    fn say(&self, line: Option<Line>) {
+        self.lock().line = line;
+    }
+}
+
+/// queues every cutscene whose trigger is met and which didn't play yet
+pub fn start_triggered(s: &mut PersistentState, f: &mut FleetingState) {
+    for (i, cutscene) in s.cutscenes.iter().enumerate() {
+        if s.g.played_cutscenes.contains(&i) || !cutscene.trigger.is_met(&s.g) {
+            continue;
+        }
+        s.g.played_cutscenes.insert(i);
+        // counted right away so the player can't act before the cutscene starts
+        f.stage.lock().playing += 1;
+        let steps = cutscene.steps.clone();
+        let (frame, stage) = (f.frame.clone(), f.stage.clone());
+        // pans stay on the map, the player can look anywhere
+        let map =
+            Rect::wh(s.ground.width as f32 * GRIDSIZE, s.ground.height as f32 * GRIDSIZE);
+        f.co.queue(move |mut s| async move {
+            stage.lock().bounds = Some(map);
+            for step in &steps {
+                play_step(&mut s, &frame, &stage, step).await;
+            }
+            let mut shared = stage.lock();
+            shared.bounds = None;
+            shared.playing -= 1;
+        });
+    }
+}
+
+async fn play_step(
+    s: &mut CosyncInput<PersistentState>,
+    frame: &FrameChannel,
+    stage: &Stage,
+    step: &Step,
+) {
+    match step {
+        Step::Say { speaker, portrait, text } => {
+            let line = Line {
+                speaker: speaker.clone(),
+                portrait: portrait.clone(),
+                text: text.clone(),
+            };
+            stage.say(Some(line));
+            frame.wait_for_confirm().await;
+            stage.say(None);
+        }
+        Step::Pan { tile, duration } => {
+            let to = game::centered(game_to_world(pos(*tile)));
+            let duration = *duration;
+            frame.with_context(move |c| c.move_camera_to(to, duration));
+            frame.wait_seconds(duration).await;
+        }
+        Step::Zoom { zoom, duration } => {
+            let (zoom, duration) = (*zoom, *duration);
+            frame.with_context(move |c| c.zoom_camera_to(zoom, duration));
+            frame.wait_seconds(duration).await;
+        }
+        Step::Shake { strength, duration } => {
+            let (strength, duration) = (*strength, *duration);
+            frame.with_context(move |c| c.shake_camera(strength, duration));
+        }
+        Step::Move { path } => {
+            let path: Vec<Pos> = path.iter().copied().map(pos).collect();
+            let Some(&start) = path.first() else {
+                return;
+            };
+            if let Some(key) = actor_key(s, start) {
+                stage.lock().following = Some(key);
+                game::move_unit(s, frame, key, &path).await;
+                stage.lock().following = None;
+            }
+        }
+        Step::Animate { tile, animation } => {
+            let key = actor_key(s, pos(*tile));
+            let duration = s.get().animations.get(animation).map(|a| a.duration());
+            if let (Some(key), Some(duration)) = (key, duration) {
+                stage.lock().animations.push((key, animation.clone()));
+                frame.wait_seconds(duration).await;
+            }
+        }
+        Step::Wait { seconds } => frame.wait_seconds(*seconds).await,
+        Step::WaitConfirm => frame.wait_for_confirm().await,
+    }
+}
+
+fn actor_key(s: &mut CosyncInput<PersistentState>, pos: Pos) -> Option<Key<Actor>> {
+    s.get().g.actors.iter_keys().find(|(_, a)| a.pos == pos).map(|(k, _)| k)
+}
+
+/// box along the bottom of the screen, the portrait on the left
+pub fn draw_dialogue(c: &mut dyn ContextTrait, s: &PersistentState, line: &Line) {
+    c.set_space(Space::Screen);
+    let screen = c.screen_rect();
+    let portrait = GRIDSIZE * PORTRAIT_SCALE;
+    let content = Rect::new(20., screen.h - portrait - 20., screen.w - 40., portrait);
+    ui::panel(c, content, Z_LEVEL);
+    if let Some(sprite) = s.sprites.get(&line.portrait) {
+        let params = DrawParams::new().scale(PORTRAIT_SCALE);
+        sprite.draw_ex(c, content.x, content.y, &params, Z_LEVEL);
+    }
+    let text = content.skip_left(portrait + ui::PADDING);
+    ui::label(c, &line.speaker, &SPEAKER, text.x, text.y, Z_LEVEL);
+    let speech = SPEECH.wrap(text.w);
+    c.draw_text(&line.text, &speech, text.x, text.y + ui::ROW_HEIGHT, Z_LEVEL);
+    c.set_space(Space::World);
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{
+        headless::{DrawCall, HeadlessContext},
+        input::Key,
+        FPos,
+    };
+
+    use super::*;
+    use crate::game::update_inner;
+
+    fn cutscenes() -> Vec<Cutscene> {
+        let json = r#"[
+            { "trigger": "LevelStart", "steps": [
+                { "Pan": { "tile": [5, 3], "duration": 0.5 } },
+                { "Say": { "speaker": "Red", "portrait": "red_infantry", "text": "Halt!" } },
+                { "Shake": { "strength": 1, "duration": 0.3 } },
+                { "Move": { "path": [[5, 3], [5, 4], [5, 5]] } }
+            ] },
+            { "trigger": { "Turn": { "turn": 1 } }, "steps": [
+                { "Wait": { "seconds": 0.2 } },
+                "WaitConfirm"
+            ] },
+            { "trigger": { "Reach": { "tile": [5, 12] } }, "steps": ["WaitConfirm"] }
+        ]"#;
+        DeJson::deserialize_json(json).unwrap()
+    }
+
+    fn setup() -> (HeadlessContext, PersistentState, FleetingState) {
+        let mut c = HeadlessContext::new();
+        c.add_texture("tiles", 192., 176.);
+        c.add_texture("ui_bg", 192., 64.);
+        // small enough to move around inside the map
+        c.view = Rect::wh(160., 90.);
+        let mut s = PersistentState::new();
+        s.cutscenes = cutscenes();
+        (c, s, FleetingState::new())
+    }
+
+    fn shows_text(c: &HeadlessContext, expected: &str) -> bool {
+        c.draws
+            .iter()
+            .any(|d| matches!(&d.call, DrawCall::Text { text, .. } if text == expected))
+    }
+
+    #[test]
+    fn level_start_cutscene() {
+        let (mut c, mut s, mut f) = setup();
+        let red = s.g.actors.iter_keys().find(|(_, a)| a.pos == Pos::new(5, 3)).unwrap().0;
+        c.run_frames(40, |c| update_inner(c, &mut s, &mut f));
+        assert!(f.stage.is_playing());
+        assert!(c.camera_bounds.is_some());
+        assert_eq!(FPos { x: 5.5 * GRIDSIZE, y: 3.5 * GRIDSIZE }, c.view.center());
+        assert!(shows_text(&c, "Halt!"));
+        // the buttons are hidden and clicks don't reach the map
+        assert!(!shows_text(&c, "End turn"));
+
+        c.press_key(Key::Enter);
+        c.run_frames(60, |c| update_inner(c, &mut s, &mut f));
+        assert!(!shows_text(&c, "Halt!"));
+        assert_eq!(Pos::new(5, 5), s.g.actors[red].pos);
+        assert_eq!(vec!["step"; 2], c.sounds_played());
+        assert_eq!(Some((1., 0.3)), c.shake);
+        // the camera followed the unit
+        let center = c.view.center();
+        assert!((center.y - 5.5 * GRIDSIZE).abs() < GRIDSIZE, "{center:?}");
+        assert_eq!(None, c.follow);
+        assert!(!f.stage.is_playing());
+        assert_eq!(None, c.camera_bounds);
+        assert!(shows_text(&c, "End turn"));
+
+        // played cutscenes don't start again
+        c.run_frames(10, |c| update_inner(c, &mut s, &mut f));
+        assert!(!f.stage.is_playing());
+    }
+
+    #[test]
+    fn confirm_ending_a_cutscene_stays_in_it() {
+        let (mut c, mut s, mut f) = setup();
+        s.cutscenes = DeJson::deserialize_json(
+            r#"[{ "trigger": "LevelStart", "steps": ["WaitConfirm"] }]"#,
+        )
+        .unwrap();
+        let blue = game::centered(game_to_world(Pos::new(5, 11)));
+        c.set_mouse(blue);
+        c.run_frames(2, |c| update_inner(c, &mut s, &mut f));
+        assert!(f.stage.is_playing());
+
+        c.press_key(Key::Enter);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(!f.stage.is_playing());
+        assert!(matches!(s.g.selection, game::Selection::None));
+    }
+
+    #[test]
+    fn reload_frees_the_camera() {
+        let (mut c, mut s, mut f) = setup();
+        c.run_frames(2, |c| update_inner(c, &mut s, &mut f));
+        assert!(c.camera_bounds.is_some());
+
+        // a reload drops the running cutscene with the rest of the fleeting state
+        let mut f = FleetingState::new();
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert_eq!(None, c.camera_bounds);
+    }
+
+    #[test]
+    fn animation_plays_once() {
+        let (mut c, mut s, mut f) = setup();
+        s.cutscenes = DeJson::deserialize_json(
+            r#"[{ "trigger": "LevelStart", "steps": [
+                { "Animate": { "tile": [5, 11], "animation": "blue_infantry_attack" } }
+            ] }]"#,
+        )
+        .unwrap();
+        c.view = Rect::wh(640., 360.);
+        let start = Pos::new(5, 11);
+        let world = game_to_world(start);
+        let key = s.g.actors.iter_keys().find(|(_, a)| a.pos == start).unwrap().0;
+        let sprite = s.sprites[&s.g.actors[key].sprite].src;
+        let drawn_x = |c: &HeadlessContext| {
+            c.draws.iter().find_map(|d| match &d.call {
+                DrawCall::TexturePart { src, x, y, .. }
+                    if *src == sprite && (world.y - y).abs() <= 1. =>
+                {
+                    Some(*x)
+                }
+                _ => None,
+            })
+        };
+
+        c.run_frames(3, |c| update_inner(c, &mut s, &mut f));
+        assert_eq!(Some(world.x + 2.), drawn_x(&c));
+        assert!(f.stage.is_playing());
+        c.run_frames(60, |c| update_inner(c, &mut s, &mut f));
+        assert_eq!(Some(world.x), drawn_x(&c));
+        assert!(!f.stage.is_playing());
+    }
+
+    #[test]
+    fn turn_and_reach_triggers() {
+        let (mut c, mut s, mut f) = setup();
+        s.g.played_cutscenes.insert(0);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(!f.stage.is_playing());
+
+        c.press_key(Key::E);
+        c.run_frames(2, |c| update_inner(c, &mut s, &mut f));
+        assert!(f.stage.is_playing());
+        // confirming during the wait is too early
+        c.press_key(Key::Enter);
+        c.run_frames(2, |c| update_inner(c, &mut s, &mut f));
+        assert!(f.stage.is_playing());
+        c.run_frames(30, |c| update_inner(c, &mut s, &mut f));
+        c.press_key(Key::Enter);
+        c.run_frames(2, |c| update_inner(c, &mut s, &mut f));
+        assert!(!f.stage.is_playing());
+
+        let blue = s.g.actors.iter_keys().find(|(_, a)| a.pos == Pos::new(5, 11)).unwrap().0;
+        s.g.actors[blue].pos = Pos::new(5, 12);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(f.stage.is_playing());
+    }
+}
@@ -15,7 +15,7 @@ pub fn dijkstra<F: Fn(Pos) -> i32>(grid: &mut Grid<i32>, seed: &[Pos], cost: F)
     next.extend(seed.iter()); // sometimes its necessary to recompute seeds too
 
     while !next.is_empty() {
-        let buffer: Vec<_> = next.drain(..).collect();
+        let buffer: Vec<_> = std::mem::take(&mut next);
         for pos in buffer.into_iter() {
             let neighbor_max = {
                 get_neighbors(pos, grid)
@@ -77,6 +77,12 @@ pub fn dijkstra_path(grid: &Grid<i32>, start: Pos) -> Vec<Pos> {
 mod tests {
     use super::*;
 
+    /// compares whole maps, so failures print both of them
+    fn assert_grid(expected: &str, grid: &Grid<i32>) {
+        let expected: Grid<i32> = Grid::from_text(expected).unwrap();
+        assert_eq!(expected.to_text(), grid.to_text());
+    }
+
     #[test]
     fn get_neighbors_test() {
         let grid = Grid::new(10, 10, 0);
@@ -100,15 +106,38 @@ mod tests {
         let pos = Pos::new(5, 5);
         *grid.get_clamped_mut(pos.x, pos.y) = 5;
         dijkstra(&mut grid, &[pos], |_| 1);
-        assert_eq!(2, *grid.get(2, 5));
+        let expected = "
+            0 0 0 0 0 0 0 0 0 0
+            0 0 0 0 0 1 0 0 0 0
+            0 0 0 0 1 2 1 0 0 0
+            0 0 0 1 2 3 2 1 0 0
+            0 0 1 2 3 4 3 2 1 0
+            0 1 2 3 4 5 4 3 2 1
+            0 0 1 2 3 4 3 2 1 0
+            0 0 0 1 2 3 2 1 0 0
+            0 0 0 0 1 2 1 0 0 0
+            0 0 0 0 0 1 0 0 0 0
+        ";
+        assert_grid(expected, &grid);
 
         // higher cost
         let mut grid = Grid::new(10, 10, 0);
         let pos = Pos::new(5, 5);
         *grid.get_clamped_mut(pos.x, pos.y) = 5;
         dijkstra(&mut grid, &[pos], |_| 2);
-        assert_eq!(0, *grid.get(2, 5));
-        assert_eq!(1, *grid.get(3, 5));
+        let expected = "
+            0 0 0 0 0 0 0 0 0 0
+            0 0 0 0 0 0 0 0 0 0
+            0 0 0 0 0 0 0 0 0 0
+            0 0 0 0 0 1 0 0 0 0
+            0 0 0 0 1 3 1 0 0 0
+            0 0 0 1 3 5 3 1 0 0
+            0 0 0 0 1 3 1 0 0 0
+            0 0 0 0 0 1 0 0 0 0
+            0 0 0 0 0 0 0 0 0 0
+            0 0 0 0 0 0 0 0 0 0
+        ";
+        assert_grid(expected, &grid);
 
         // multiple seeds
         let mut grid = Grid::new(10, 10, 0);
@@ -117,6 +146,42 @@ mod tests {
         let pos2 = Pos::new(1, 4);
         *grid.get_clamped_mut(pos2.x, pos2.y) = 5;
         dijkstra(&mut grid, &[pos, pos2], |_| 1);
-        assert_eq!(3, *grid.get(2, 5));
+        let expected = "
+            0 1 0 0 0 0 0 0 0 0
+            1 2 1 0 0 1 0 0 0 0
+            2 3 2 1 1 2 1 0 0 0
+            3 4 3 2 2 3 2 1 0 0
+            4 5 4 3 3 4 3 2 1 0
+            3 4 3 3 4 5 4 3 2 1
+            2 3 2 2 3 4 3 2 1 0
+            1 2 1 1 2 3 2 1 0 0
+            0 1 0 0 1 2 1 0 0 0
+            0 0 0 0 0 1 0 0 0 0
+        ";
+        assert_grid(expected, &grid);
+    }
+
+    #[test]
+    fn dijkstra_board_test() {
+        // water is impassable, so the right side has to be reached around the lake
+        let board = Grid::from_ascii(
+            "
+            ..~..
+            ..~..
+            .....
+            ",
+            |c| c,
+        )
+        .unwrap();
+        let mut grid = Grid::new(board.width, board.height, 0);
+        let pos = Pos::new(0, 0);
+        grid[pos] = 9;
+        dijkstra(&mut grid, &[pos], |p| if board[p] == '~' { 9999 } else { 1 });
+        let expected = "
+            9 8 0 2 1
+            8 7 0 3 2
+            7 6 5 4 3
+        ";
+        assert_grid(expected, &grid);
     }
 }
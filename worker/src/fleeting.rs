@@ -1,22 +1,43 @@
-use cosync::CosyncInput;
+use base::{FPos, Pos};
 
-use crate::persistent::PersistentState;
+use crate::{
+    animation::AnimationPlayer, cutscene::Stage, frame::FrameChannel, game::Actor,
+    genarena::Key, persistent::PersistentState, rebind::RebindScreen, ui::Menu,
+};
 
 /// dropped and recreated on reload
 /// you can change this definition without breaking hotreloading
 pub struct FleetingState {
     pub co: cosync::Cosync<PersistentState>,
+    /// input for the coroutines and sounds they queue, they get a clone of it
+    pub frame: FrameChannel,
+    /// dialogue of the running cutscene
+    pub stage: Stage,
+    /// mouse position of the last frame, the tile cursor only follows the mouse when it moves
+    pub last_mouse: Option<FPos>,
+    pub rebind: RebindScreen,
+    /// tile the last left mouse press happened on, used for drag to move
+    pub drag_from: Option<Pos>,
+    /// unit shown in the info panel, opened by double clicking
+    pub info: Option<Key<Actor>>,
+    /// animations of the units, they start over after a reload
+    pub animations: AnimationPlayer<Key<Actor>>,
+    /// orders for a unit that moved, it has the keyboard while shown
+    pub menu: Menu,
 }
 
 impl FleetingState {
     pub fn new() -> Self {
-        let mut co = cosync::Cosync::new();
-        co.queue(move |mut input: CosyncInput<PersistentState>| async move {
-            for _ in 0..5 {
-                cosync::sleep_ticks(30).await;
-                let mut _s = input.get();
-            }
-        });
-        Self { co }
+        Self {
+            co: cosync::Cosync::new(),
+            frame: FrameChannel::default(),
+            stage: Stage::default(),
+            last_mouse: None,
+            rebind: RebindScreen::default(),
+            drag_from: None,
+            info: None,
+            animations: AnimationPlayer::default(),
+            menu: Menu::default(),
+        }
     }
 }
@@ -0,0 +1,148 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use base::{input::Action, Button, ContextTrait, FPos};
+
+/// what coroutines get to see of the context, captured every frame before they run
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameInput {
+    pub delta: f32,
+    pub time: f64,
+    pub mouse_world: FPos,
+    pub mouse_screen: FPos,
+    /// indexed by [Button::index]
+    pub pressed: [bool; 3],
+    /// the confirm action or a left click
+    pub confirm: bool,
+}
+
+impl FrameInput {
+    pub fn capture(c: &dyn ContextTrait) -> Self {
+        FrameInput {
+            delta: c.delta(),
+            time: c.time(),
+            mouse_world: c.mouse_world(),
+            mouse_screen: c.mouse_screen(),
+            pressed: Button::ALL.map(|b| c.is_pressed(b)),
+            confirm: c.is_action_pressed(Action::Confirm) || c.is_pressed(Button::MouseLeft),
+        }
+    }
+}
+
+type Command = Box<dyn FnOnce(&mut dyn ContextTrait) + Send>;
+
+#[derive(Default)]
+struct Shared {
+    input: FrameInput,
+    sounds: Vec<&'static str>,
+    commands: Vec<Command>,
+}
+
+/// coroutines can not reach the context, they keep a clone of this instead
+/// it lives in fleeting state, so nothing of it ends up in saved state
+#[derive(Clone, Default)]
+pub struct FrameChannel(Arc<Mutex<Shared>>);
+
+impl FrameChannel {
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.0.lock().unwrap()
+    }
+
+    /// input of the current frame
+    pub fn input(&self) -> FrameInput {
+        self.lock().input
+    }
+
+    /// queued sounds are played at the end of the frame
+    pub fn play_sound(&self, name: &'static str) {
+        self.lock().sounds.push(name);
+    }
+
+    /// runs `f` with the context at the end of the frame, in the order of the calls
+    pub fn with_context(&self, f: impl FnOnce(&mut dyn ContextTrait) + Send + 'static) {
+        self.lock().commands.push(Box::new(f));
+    }
+
+    /// should be called every frame before the coroutines run
+    pub fn begin(&self, c: &dyn ContextTrait) {
+        self.lock().input = FrameInput::capture(c);
+    }
+
+    /// should be called every frame after the coroutines ran
+    pub fn end(&self, c: &mut dyn ContextTrait) {
+        let (sounds, commands) = {
+            let mut shared = self.lock();
+            (std::mem::take(&mut shared.sounds), std::mem::take(&mut shared.commands))
+        };
+        for sound in sounds {
+            c.play_sound(sound);
+        }
+        for command in commands {
+            command(c);
+        }
+    }
+
+    /// waits a tick and returns the input of the new frame
+    pub async fn next_frame(&self) -> FrameInput {
+        cosync::sleep_ticks(1).await;
+        self.input()
+    }
+
+    /// waits until the player confirms, with the keyboard or a click
+    pub async fn wait_for_confirm(&self) {
+        while !self.next_frame().await.confirm {}
+    }
+
+    /// waits the given number of seconds of frame time
+    pub async fn wait_seconds(&self, seconds: f32) {
+        let mut waited = 0.;
+        while waited < seconds {
+            waited += self.next_frame().await.delta;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::headless::HeadlessContext;
+    use cosync::Cosync;
+
+    use super::*;
+
+    #[test]
+    fn coroutines_see_frame_input() {
+        let mut c = HeadlessContext::new();
+        let channel = FrameChannel::default();
+        let mut co: Cosync<Vec<FPos>> = Cosync::new();
+        let frame = channel.clone();
+        co.queue(move |mut clicks| async move {
+            let input = loop {
+                let input = frame.next_frame().await;
+                if input.confirm {
+                    break input;
+                }
+            };
+            clicks.get().push(input.mouse_world);
+            frame.play_sound("select");
+            frame.wait_seconds(0.5).await;
+            frame.play_sound("step");
+        });
+
+        let mut clicks = Vec::new();
+        let mut run = |c: &mut HeadlessContext| {
+            c.frame(|c| {
+                channel.begin(c);
+                co.run_until_stall(&mut clicks);
+                channel.end(c);
+            })
+        };
+        run(&mut c);
+        c.set_mouse(FPos { x: 3., y: 4. });
+        c.press(Button::MouseLeft);
+        run(&mut c);
+        for _ in 0..40 {
+            run(&mut c);
+        }
+        assert_eq!(vec!["select", "step"], c.sounds_played());
+        assert_eq!(vec![FPos { x: 3., y: 4. }], clicks);
+    }
+}
@@ -2,34 +2,54 @@ use std::collections::{HashMap, HashSet};
 
 use base::{
     grids::Grid,
+    input::Action,
     ldtk::{EntityDef, EntityOnMap, GroundType, Team, TerrainType, UnitType},
-    Button, Color, ContextTrait, FPos, Pos, Rect,
+    text::TextStyle,
+    Button, Color, ContextTrait, DrawParams, FPos, Pos, Rect, Space,
 };
+use cosync::CosyncInput;
 use nanoserde::DeJson;
 
 use crate::{
+    cutscene,
     dijkstra::{dijkstra, dijkstra_path},
     fleeting::FleetingState,
+    frame::FrameChannel,
     genarena::{GenArena, Key},
     persistent::PersistentState,
-    util::{game_to_world, grid_world_pos, world_to_game},
+    tween::{self, Ease, Tween},
+    ui::{self, Menu},
+    util::{game_to_world, world_rect_to_game, world_to_game},
     GRIDSIZE,
 };
 
 const HP_MAX: i32 = 10;
-pub const ENEMY_TEAM: Team = Team::Red;
 pub const PLAYER_TEAM: Team = Team::Blue;
 
+const TEXT: TextStyle = TextStyle::new(6.);
+/// loaded from assets/sound on the first frame, "music" loops from then on
+const SOUNDS: [&str; 4] = ["step", "select", "attack", "music"];
+/// seconds a unit takes to move a single tile
+const STEP_TIME: f32 = 0.08;
+/// units that already acted this turn are greyed out
+const MOVED: DrawParams = DrawParams::new().tint(Color::rgb(0.5, 0.5, 0.5));
+
 pub struct GameState {
     pub actors: GenArena<Actor>,
     pub selection: Selection,
+    /// tile the player is pointing at, driven by mouse or keyboard
+    pub cursor: Pos,
+    pub turn: i32,
+    /// indices into [PersistentState::cutscenes], each one plays once
+    pub played_cutscenes: HashSet<usize>,
 }
 
 pub enum Selection {
     None,
     Selected(Key<Actor>),
     Moving(Key<Actor>),
-    Confirm(Key<Actor>),
+    /// moved and waiting for an order, with the tile it came from
+    Confirm(Key<Actor>, Pos),
 }
 
 pub struct Actor {
@@ -72,72 +92,163 @@ impl GameState {
                 actors.push(a);
             }
         }
-        GameState { actors, selection: Selection::None }
+        GameState {
+            actors,
+            selection: Selection::None,
+            cursor: Pos::new(0, 0),
+            turn: 0,
+            played_cutscenes: HashSet::new(),
+        }
+    }
+
+    /// next unit of the player which can still move, cycling through them in order
+    fn next_unit(&self, after: Option<Key<Actor>>) -> Option<Key<Actor>> {
+        let movable: Vec<Key<Actor>> = self
+            .actors
+            .iter_keys()
+            .filter(|(_, a)| a.team == PLAYER_TEAM && !a.has_moved)
+            .map(|(key, _)| key)
+            .collect();
+        let index = after.and_then(|after| movable.iter().position(|key| *key == after));
+        match index {
+            Some(i) => movable.get((i + 1) % movable.len()).copied(),
+            None => movable.first().copied(),
+        }
+    }
+
+    fn end_turn(&mut self) {
+        for actor in self.actors.iter_mut() {
+            actor.has_moved = false;
+        }
+        self.selection = Selection::None;
+        self.turn += 1;
     }
 }
 
 pub fn update_inner(c: &mut dyn ContextTrait, s: &mut PersistentState, f: &mut FleetingState) {
-    s.delta = c.delta();
+    if !s.audio_started {
+        for name in SOUNDS {
+            c.load_sound(name, &format!("../assets/sound/{name}.wav"));
+        }
+        c.play_music("music");
+        s.audio_started = true;
+    }
+    cutscene::start_triggered(s, f);
+    // the input ending a cutscene belongs to it, not to the game
+    let in_cutscene = f.stage.is_playing();
+    f.frame.begin(c);
     f.co.run_until_stall(s);
-    //c.load_texture("ui_arrow", "../assets/PNG/Blue/Default/arrow_basic_e_small.png");
+    f.frame.end(c);
+    let following = f.stage.following().and_then(|key| s.g.actors.get(key));
+    c.follow_camera(following.map(|a| centered(a.draw_pos)));
+    c.set_camera_bounds(f.stage.camera_bounds());
+    update_cursor(c, s, f);
+    let clicked = c.is_pressed(Button::MouseLeft);
+    let double_click = clicked && c.click_count(Button::MouseLeft) == 2;
+    let confirm = (clicked || c.is_action_pressed(Action::Confirm)) && !double_click;
+    if clicked {
+        f.drag_from = Some(s.g.cursor);
+    }
+    //c.draw_rect(rect, c, z_level);
+    // TODO add Z to draw text
+    // c.draw_text("Hello World asfsj", 0., 500., 50);
 
-    let h = 9.;
-    let x = 100.;
-    let ystart = 180.;
-    let mut y = ystart;
-    let r1: Rect = c.draw_text("Attack!", 6., x, y, 150);
-    y += h;
-    let r2 = c.draw_text("Wait!", 6., x, y, 150);
-    y += h;
-    let r3: Rect = c.draw_text("Cancel!", 6., x, y, 150);
-    let r = r1.fuse(r3).grow_all(5.);
-    draw_nine_patch(c, "ui_bg", 5., r);
+    c.draw_tile_layer("ground", "tiles", &s.ground_tiles, 0);
+    c.draw_tile_layer("terrain", "tiles", &s.terrain_tiles, 1);
 
-    let mouse = c.mouse_world();
-    let y = match (r1.contains(mouse), r2.contains(mouse), r3.contains(mouse)) {
-        (true, _, _) | (false, false, false) => ystart - h,
-        (false, true, _) => ystart,
-        (false, false, true) => ystart + h,
-    };
-    s.sprites["pointer"].draw(c, x - 15., y, 300);
+    let view = c.visible_world_rect();
+    f.animations.retain(|key| s.g.actors.get(*key).is_some());
+    for (key, name) in f.stage.take_animations() {
+        f.animations.play(key, &name, c.time());
+    }
+    for (key, actor) in s.g.actors.iter_keys() {
+        let pos = actor.draw_pos;
+        if !view.overlaps(Rect::new(pos.x, pos.y, GRIDSIZE, GRIDSIZE)) {
+            continue;
+        }
+        let params = if actor.has_moved { MOVED } else { DrawParams::new() };
+        let state = match s.g.selection {
+            Selection::Moving(k) if k == key => "move",
+            _ => "idle",
+        };
+        let current = format!("{}_{state}", actor.sprite);
+        let (name, elapsed) = f.animations.current(key, &current, c.time(), &s.animations);
+        match s.animations.get(name) {
+            Some(animation) => animation.draw(c, &s.sprites, elapsed, pos, &params, 10),
+            None => s.sprites[&actor.sprite].draw_ex(c, pos.x, pos.y, &params, 10),
+        }
+    }
 
-    let r: Rect = c.draw_text("Attack!", 6., 100., 580., 150);
-    let color = Color::rgb(0.5, 0.5, 1.0);
-    c.draw_rect(r.grow_all(3.), color, 120);
-    let color = Color::rgb(0.8, 0.8, 1.0);
-    c.draw_rect(r.grow_all(4.), color, 110);
-    let color = Color::rgb(0., 0., 1.0);
-    c.draw_rect(r.grow_all(5.), color, 100);
+    // cutscenes take the input until they are done
+    if in_cutscene {
+        if let Some(line) = f.stage.line() {
+            cutscene::draw_dialogue(c, s, &line);
+        }
+        return;
+    }
 
-    //c.draw_rect(rect, c, z_level);
-    // TODO add Z to draw text
-    // c.draw_text("Hello World asfsj", 0., 500., 50);
+    //c.load_texture("ui_arrow", "../assets/PNG/Blue/Default/arrow_basic_e_small.png");
+
+    // buttons stay in place while the camera moves
+    c.set_space(Space::Screen);
+    let screen = c.screen_rect();
+    let width = c.measure_text("End turn", &ui::TEXT).w;
+    let end_turn = ui::button(c, "End turn", screen.w - width - 20., screen.h - 25., 150);
+    if end_turn.hovered {
+        let binding = c.input_map().get(Action::EndTurn).and_then(|b| b.bindings().next());
+        let tip =
+            binding.map_or("Ends the turn".to_string(), |b| format!("Ends the turn ({b})"));
+        ui::tooltip(c, &tip, 400);
+    }
+    c.set_space(Space::World);
+    // clicks on the ui don't reach the map
+    let confirm = confirm && !(clicked && end_turn.hovered);
 
-    for tile in &s.ground_tiles {
-        c.draw_texture_part("tiles", tile.source_rect, tile.pos.x, tile.pos.y, 0);
+    if f.rebind.update(c, s) {
+        return;
     }
 
-    for tile in &s.terrain_tiles {
-        c.draw_texture_part("tiles", tile.source_rect, tile.pos.x, tile.pos.y, 1);
+    if double_click {
+        let cursor = s.g.cursor;
+        f.info = s.g.actors.iter_keys().find(|(_, a)| a.pos == cursor).map(|(key, _)| key);
+        if f.info.is_some() {
+            s.g.selection = Selection::None;
+        }
+    } else if confirm || c.is_action_pressed(Action::Cancel) {
+        f.info = None;
+    }
+    if let Some(key) = f.info {
+        draw_unit_info(c, s, key);
     }
 
-    for actor in s.g.actors.iter() {
-        let sprite = &s.sprites[&actor.sprite];
-        sprite.draw(c, actor.draw_pos.x, actor.draw_pos.y, 10);
+    // units that are moving or waiting for an order finish first
+    let idle = matches!(s.g.selection, Selection::None | Selection::Selected(_));
+    if (c.is_action_pressed(Action::EndTurn) || end_turn.clicked) && idle {
+        s.g.end_turn();
+        f.menu = Menu::default();
+    }
+    if c.is_action_pressed(Action::NextUnit) {
+        let current = match s.g.selection {
+            Selection::Selected(key) => Some(key),
+            _ => None,
+        };
+        if let Some(key) = s.g.next_unit(current).filter(|_| idle) {
+            s.g.cursor = s.g.actors[key].pos;
+            s.g.selection = Selection::Selected(key);
+            c.move_camera_to(centered(s.g.actors[key].draw_pos), 0.3);
+        }
     }
 
     match s.g.selection {
         Selection::None => {
-            let pos = grid_world_pos(c.mouse_world());
-            s.sprites["cursor"].draw(c, pos.x, pos.y, 10);
+            draw_cursor(c, s, game_to_world(s.g.cursor));
             // select actor
-            if c.is_pressed(Button::MouseLeft) {
-                let pos = world_to_game(c.mouse_world());
+            if confirm {
+                let pos = s.g.cursor;
                 if let Some((key, _)) =
-                    s.g.actors
-                        .iter_keys()
-                        .filter(|(_key, a)| a.pos == pos && a.team == Team::Blue)
-                        .next()
+                    s.g.actors.iter_keys().find(|(_key, a)| {
+                        a.pos == pos && a.team == PLAYER_TEAM && !a.has_moved
+                    })
                 {
                     s.g.selection = Selection::Selected(key);
                 }
@@ -145,7 +256,7 @@ pub fn update_inner(c: &mut dyn ContextTrait, s: &mut PersistentState, f: &mut F
         }
         Selection::Selected(key) => {
             let a = &s.g.actors[key];
-            s.sprites["cursor"].draw(c, a.draw_pos.x, a.draw_pos.y, 10);
+            draw_cursor(c, s, a.draw_pos);
 
             // draw moveable area
             let start_pos = a.pos;
@@ -153,14 +264,14 @@ pub fn update_inner(c: &mut dyn ContextTrait, s: &mut PersistentState, f: &mut F
             move_range[start_pos] = 9;
             dijkstra(&mut move_range, &[start_pos], movement_cost(s, PLAYER_TEAM));
             draw_move_range(c, s, &move_range);
+            let reachable = move_range.mask(|v| *v > 0);
 
             // find goal
             let mut grid = Grid::new(s.ground.width, s.ground.height, 0);
-            let goal = world_to_game(c.mouse_world());
-            *grid.get_clamped_mut(goal.x, goal.y) = 99; // TODO increase this when done developing
+            let goal = s.g.cursor;
+            grid[goal] = 99; // TODO increase this when done developing
             dijkstra(&mut grid, &[goal], movement_cost(s, PLAYER_TEAM));
-            move_range.clamp_values(0, 1);
-            grid.mul_inplace(&move_range);
+            let mut grid = grid.select(&reachable, 0);
 
             // allow passing through allies, but don't stop on them
             let mut seeds = Vec::new();
@@ -172,7 +283,7 @@ pub fn update_inner(c: &mut dyn ContextTrait, s: &mut PersistentState, f: &mut F
                 grid.iter_coords().max_by_key(|(_pos, val)| *val).map(|(pos, _)| pos).unwrap();
             seeds.push(highest_reachable_pos);
             dijkstra(&mut grid, &seeds, movement_cost(s, PLAYER_TEAM));
-            grid.mul_inplace(&move_range);
+            let mut grid = grid.select(&reachable, 0);
 
             // disallow moving through enemies
             for actor in s.g.actors.iter().filter(|a| a.team != PLAYER_TEAM) {
@@ -183,28 +294,18 @@ pub fn update_inner(c: &mut dyn ContextTrait, s: &mut PersistentState, f: &mut F
             let path = dijkstra_path(&grid, start_pos);
             draw_move_range(c, s, &grid);
             draw_move_path(c, s, &path);
-            if c.is_pressed(Button::MouseLeft) && path.len() > 0 {
+            // drag to move: pressed on the unit, released on the goal
+            let dropped = c.is_released(Button::MouseLeft)
+                && f.drag_from == Some(start_pos)
+                && s.g.cursor != start_pos;
+            if c.is_action_pressed(Action::Cancel) {
+                s.g.selection = Selection::None;
+            } else if (confirm || dropped) && !path.is_empty() {
                 s.g.selection = Selection::Moving(key);
+                let frame = f.frame.clone();
                 f.co.queue(move |mut s| async move {
-                    for pos in path.iter() {
-                        let target = game_to_world(*pos);
-                        let mut lerpiness = 0.;
-                        while lerpiness < 1. {
-                            {
-                                let s = &mut s.get();
-                                lerpiness += s.delta * 25.;
-                                let drawpos = &mut s.g.actors[key].draw_pos;
-                                *drawpos = drawpos.lerp(target.into(), lerpiness);
-                            }
-                            cosync::sleep_ticks(1).await;
-                        }
-                    }
-                    let last = *path.last().unwrap();
-                    let target = game_to_world(last);
-                    let s = &mut s.get();
-                    s.g.actors[key].draw_pos = target.into();
-                    s.g.actors[key].pos = last;
-                    s.g.selection = Selection::Confirm(key);
+                    move_unit(&mut s, &frame, key, &path).await;
+                    s.get().g.selection = Selection::Confirm(key, start_pos);
                 });
             }
         }
@@ -212,12 +313,119 @@ pub fn update_inner(c: &mut dyn ContextTrait, s: &mut PersistentState, f: &mut F
             let _a = &s.g.actors[key];
             // TODO
         }
-        Selection::Confirm(key) => {
-            let a = &s.g.actors[key];
-            // TODO
-            s.g.selection = Selection::None;
+        Selection::Confirm(key, from) => {
+            draw_cursor(c, s, s.g.actors[key].draw_pos);
+            c.set_space(Space::Screen);
+            f.menu.focused = true;
+            let entries = ["Attack", "Wait", "Undo"];
+            let menu = f.menu.show(c, &s.sprites["pointer"], &entries, 100., 170., 150);
+            ui::panel(c, menu.rect, 150);
+            c.set_space(Space::World);
+            let undo = menu.activated == Some(2) || c.is_action_pressed(Action::Cancel);
+            if menu.activated == Some(0) {
+                c.play_sound("attack");
+                c.shake_camera(2., 0.2);
+                let attack = format!("{}_attack", s.g.actors[key].sprite);
+                f.animations.play(key, &attack, c.time());
+                s.g.actors[key].has_moved = true;
+                s.g.selection = Selection::None;
+            } else if menu.activated == Some(1) {
+                c.play_sound("select");
+                s.g.actors[key].has_moved = true;
+                s.g.selection = Selection::None;
+            } else if undo {
+                let actor = &mut s.g.actors[key];
+                actor.pos = from;
+                actor.draw_pos = game_to_world(from);
+                s.g.cursor = from;
+                s.g.selection = Selection::Selected(key);
+            }
+            if !matches!(s.g.selection, Selection::Confirm(..)) {
+                f.menu = Menu::default();
+            }
+        }
+    }
+}
+
+/// walks the unit along the path tile by tile, the path starts at its current tile
+pub async fn move_unit(
+    s: &mut CosyncInput<PersistentState>,
+    frame: &FrameChannel,
+    key: Key<Actor>,
+    path: &[Pos],
+) {
+    let Some(&last) = path.last() else {
+        return;
+    };
+    for pos in path.iter().skip(1) {
+        let from = s.get().g.actors[key].draw_pos;
+        let step = Tween::new(from, game_to_world(*pos), STEP_TIME, Ease::Linear);
+        tween::play(s, frame, step, |s, p| s.g.actors[key].draw_pos = p).await;
+        frame.play_sound("step");
+    }
+    let s = &mut s.get();
+    s.g.actors[key].draw_pos = game_to_world(last);
+    s.g.actors[key].pos = last;
+}
+
+/// middle of a tile drawn at pos
+pub fn centered(pos: FPos) -> FPos {
+    FPos { x: pos.x + GRIDSIZE / 2., y: pos.y + GRIDSIZE / 2. }
+}
+
+fn team_color(team: Team) -> Color {
+    match team {
+        Team::Blue => Color::rgb(0.2, 0.3, 0.9),
+        Team::Red => Color::rgb(0.8, 0.2, 0.2),
+    }
+}
+
+/// pulses while waiting for input
+fn draw_cursor(c: &mut dyn ContextTrait, s: &PersistentState, pos: FPos) {
+    let time = c.time();
+    match s.animations.get("cursor") {
+        Some(animation) => animation.draw(c, &s.sprites, time, pos, &DrawParams::new(), 10),
+        None => s.sprites["cursor"].draw(c, pos.x, pos.y, 10),
+    }
+}
+
+fn draw_unit_info(c: &mut dyn ContextTrait, s: &PersistentState, key: Key<Actor>) {
+    let Some(a) = s.g.actors.get(key) else {
+        return;
+    };
+    let status = if a.has_moved { "Moved" } else { "Ready" };
+    let details = format!("Team {:?}\nHP {}/{}\n{status}", a.team, a.hp, HP_MAX);
+    let title = TEXT.color(team_color(a.team));
+    let x = a.draw_pos.x + GRIDSIZE + 10.;
+    let y = a.draw_pos.y + TEXT.line_height();
+    let r = c.draw_text(&format!("{:?}", a.unit_type), &title, x, y, 150);
+    let r = r.fuse(c.draw_text(&details, &TEXT, x, y + TEXT.line_height(), 150));
+    ui::panel(c, r, 150);
+}
+
+/// the tile cursor follows the mouse when it moves and the cursor actions otherwise
+fn update_cursor(c: &mut dyn ContextTrait, s: &mut PersistentState, f: &mut FleetingState) {
+    let mouse = c.mouse_world();
+    let mut cursor = s.g.cursor;
+    if f.last_mouse != Some(mouse) || c.is_pressed(Button::MouseLeft) {
+        cursor = world_to_game(mouse);
+    }
+    f.last_mouse = Some(mouse);
+
+    for (action, (dx, dy)) in [
+        (Action::CursorUp, (0, -1)),
+        (Action::CursorDown, (0, 1)),
+        (Action::CursorLeft, (-1, 0)),
+        (Action::CursorRight, (1, 0)),
+    ] {
+        if c.is_action_pressed(action) {
+            cursor = Pos::new(cursor.x + dx, cursor.y + dy);
         }
     }
+
+    cursor.x = cursor.x.clamp(0, s.ground.width - 1);
+    cursor.y = cursor.y.clamp(0, s.ground.height - 1);
+    s.g.cursor = cursor;
 }
 
 fn movement_cost<'a>(s: &'a PersistentState, team: Team) -> impl Fn(Pos) -> i32 + 'a {
@@ -246,10 +454,12 @@ fn movement_cost<'a>(s: &'a PersistentState, team: Team) -> impl Fn(Pos) -> i32
 }
 
 fn draw_move_range(c: &mut dyn ContextTrait, s: &PersistentState, grid: &Grid<i32>) {
-    for (x, y, v) in grid.iter() {
+    let (min, w, h) = world_rect_to_game(c.visible_world_rect());
+    let visible = grid.view(min, w, h);
+    let offset = visible.offset();
+    for (pos, v) in visible.iter_coords() {
         if *v > 0 {
-            let pos = Pos::new(x, y);
-            let pos = game_to_world(pos);
+            let pos = game_to_world(Pos::new(offset.x + pos.x, offset.y + pos.y));
             s.sprites["move_range"].draw(c, pos.x, pos.y, 2);
         }
     }
@@ -266,7 +476,7 @@ fn draw_move_path(c: &mut dyn ContextTrait, s: &PersistentState, path: &[Pos]) {
     let mut prev_direction: Option<(i32, i32)> = None;
     if let Some(mut prev) = prev {
         for pos in iter {
-            let direction = (*pos - prev).into();
+            let direction = *pos - prev;
             if let Some(prev_direction) = prev_direction {
                 let sprite = match (prev_direction, direction) {
                     (LEFT, LEFT) | (RIGHT, RIGHT) => "arrow_we",
@@ -291,7 +501,7 @@ fn draw_move_path(c: &mut dyn ContextTrait, s: &PersistentState, path: &[Pos]) {
     if len >= 2 {
         let prev = path[path.len() - 2];
         let pos = path[path.len() - 1];
-        let direction: (i32, i32) = (pos - prev).into();
+        let direction: (i32, i32) = pos - prev;
         let sprite = match direction {
             LEFT => "arrow_w",
             RIGHT => "arrow_e",
@@ -305,40 +515,376 @@ fn draw_move_path(c: &mut dyn ContextTrait, s: &PersistentState, path: &[Pos]) {
     }
 }
 
-fn draw_nine_patch(c: &mut dyn ContextTrait, texture: &str, corner: f32, trect: Rect) {
-    let z = 100;
-    let source_rect = Rect { x: 0., y: 0., w: 192., h: 64. };
-
-    // corners
-    let tl = source_rect.take_left(corner).take_top(corner);
-    let tr = source_rect.take_right(corner).take_top(corner);
-    let bl = source_rect.take_left(corner).take_bot(corner);
-    let br = source_rect.take_right(corner).take_bot(corner);
-
-    c.draw_texture_part(texture, tl, trect.x, trect.y, z);
-    c.draw_texture_part(texture, tr, trect.take_right(corner).x, trect.y, z);
-    c.draw_texture_part(texture, bl, trect.x, trect.y + trect.h - corner, z);
-    c.draw_texture_part(texture, br, trect.take_right(corner).x, trect.take_bot(corner).y, z);
-
-    // top middle
-    let source = source_rect.skip_left(corner).take_top(corner).skip_right(corner);
-    let target = trect.take_top(corner).skip_left(corner).skip_right(corner);
-    c.draw_texture_part_scaled(texture, source, target, z);
-    // bot middle
-    let source = source_rect.skip_left(corner).take_bot(corner).skip_right(corner);
-    let target = trect.take_bot(corner).skip_left(corner).skip_right(corner);
-    c.draw_texture_part_scaled(texture, source, target, z);
-    // left middle
-    let source = source_rect.skip_top(corner).take_left(corner).skip_bot(corner);
-    let target = trect.skip_top(corner).skip_bot(corner).take_left(corner);
-    c.draw_texture_part_scaled(texture, source, target, z);
-    // right middle
-    let source = source_rect.skip_top(corner).take_right(corner).skip_bot(corner);
-    let target = trect.skip_top(corner).skip_bot(corner).take_right(corner);
-    c.draw_texture_part_scaled(texture, source, target, z);
-    // center
-    let source =
-        source_rect.skip_top(corner).skip_right(corner).skip_bot(corner).skip_left(corner);
-    let target = trect.skip_top(corner).skip_right(corner).skip_bot(corner).skip_left(corner);
-    c.draw_texture_part_scaled(texture, source, target, z);
+#[cfg(test)]
+mod tests {
+    use base::{
+        headless::{assert_snapshot, DrawCall, HeadlessContext},
+        input,
+    };
+
+    use super::*;
+    use crate::sprite::Sprite;
+
+    fn setup() -> (HeadlessContext, PersistentState, FleetingState) {
+        let mut c = HeadlessContext::new();
+        c.add_texture("tiles", 192., 176.);
+        c.add_texture("ui_bg", 192., 64.);
+        let mut s = PersistentState::new();
+        // the ones from the assets would take the input
+        s.cutscenes.clear();
+        (c, s, FleetingState::new())
+    }
+
+    /// center of a tile in world coordinates
+    fn tile_center(pos: Pos) -> FPos {
+        let p = game_to_world(pos);
+        FPos { x: p.x + GRIDSIZE / 2., y: p.y + GRIDSIZE / 2. }
+    }
+
+    fn actor_at(s: &PersistentState, pos: Pos) -> Option<Key<Actor>> {
+        s.g.actors.iter_keys().find(|(_, a)| a.pos == pos).map(|(key, _)| key)
+    }
+
+    #[test]
+    fn select_and_move_unit() {
+        let (mut c, mut s, mut f) = setup();
+        let start = Pos::new(5, 11);
+        let goal = Pos::new(5, 13);
+        let key = actor_at(&s, start).unwrap();
+
+        // hovering draws the cursor, clicking selects
+        c.set_mouse(tile_center(start));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Selected(k) if k == key));
+
+        // the path to the hovered tile ends in an arrow
+        c.set_mouse(tile_center(goal));
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        let arrow = s.sprites["arrow_s"].src;
+        let goal_world = game_to_world(goal);
+        assert!(c.texture_draws("tiles").any(|d| d.call
+            == DrawCall::TexturePart {
+                name: "tiles".into(),
+                src: arrow,
+                x: goal_world.x,
+                y: goal_world.y,
+                params: DrawParams::default(),
+            }));
+
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Moving(_)));
+
+        c.run_frames(300, |c| update_inner(c, &mut s, &mut f));
+        assert!(
+            matches!(s.g.selection, Selection::Confirm(k, from) if k == key && from == start)
+        );
+        assert_eq!(goal, s.g.actors[key].pos);
+        assert_eq!(goal_world, s.g.actors[key].draw_pos);
+        // a step sound for every tile the unit walks onto
+        assert_eq!(vec!["step"; 2], c.sounds_played());
+        assert_eq!(Some("music".into()), c.music);
+
+        // waiting ends the unit's turn, the menu has the keyboard
+        c.press_key(input::Key::Down);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        c.press_key(input::Key::Enter);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert_eq!(vec!["step", "step", "select"], c.sounds_played());
+        assert!(matches!(s.g.selection, Selection::None));
+
+        // units that moved are greyed out, idle units bob up and down
+        assert!(s.g.actors[key].has_moved);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(c.texture_draws("tiles").any(|d| matches!(&d.call,
+            DrawCall::TexturePart { x, y, params, .. }
+                if *x == goal_world.x && (goal_world.y - y).abs() <= 1. && *params == MOVED)));
+    }
+
+    #[test]
+    fn menu_is_hit_in_screen_space() {
+        let (mut c, mut s, mut f) = setup();
+        let start = Pos::new(5, 11);
+        let goal = Pos::new(5, 13);
+        let key = actor_at(&s, start).unwrap();
+        c.set_mouse(tile_center(start));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        c.set_mouse(tile_center(goal));
+        c.press(Button::MouseLeft);
+        c.run_frames(300, |c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Confirm(k, _) if k == key));
+        let wait = c
+            .draws
+            .iter()
+            .find(|d| matches!(&d.call, DrawCall::Text { text, .. } if text == "Wait"));
+        assert_eq!(Space::Screen, wait.unwrap().space);
+
+        // the world position under the mouse doesn't matter
+        c.mouse_screen = FPos { x: 110., y: 183. };
+        c.mouse_world = FPos { x: -1000., y: -1000. };
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert_eq!(vec!["step", "step", "select"], c.sounds_played());
+        assert!(matches!(s.g.selection, Selection::None));
+        assert!(s.g.actors[key].has_moved);
+    }
+
+    #[test]
+    fn attack_animation_plays_once() {
+        let (mut c, mut s, mut f) = setup();
+        let start = Pos::new(5, 11);
+        let goal = Pos::new(5, 13);
+        let key = actor_at(&s, start).unwrap();
+        let world = game_to_world(goal);
+        let sprite = s.sprites[&s.g.actors[key].sprite].src;
+        let drawn_x = |c: &HeadlessContext| {
+            c.draws.iter().find_map(|d| match &d.call {
+                DrawCall::TexturePart { src, x, y, .. }
+                    if *src == sprite && (world.y - y).abs() <= 1. =>
+                {
+                    Some(*x)
+                }
+                _ => None,
+            })
+        };
+        c.set_mouse(tile_center(start));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        c.set_mouse(tile_center(goal));
+        c.press(Button::MouseLeft);
+        c.run_frames(300, |c| update_inner(c, &mut s, &mut f));
+        assert_eq!(Some(world.x), drawn_x(&c));
+
+        // "Attack" is the first entry of the unit's menu
+        c.press_key(input::Key::Enter);
+        c.run_frames(3, |c| update_inner(c, &mut s, &mut f));
+        assert_eq!(Some(world.x + 2.), drawn_x(&c));
+        assert_eq!(vec!["step", "step", "attack"], c.sounds_played());
+        assert_eq!(Some((2., 0.2)), c.shake);
+        assert!(s.g.actors[key].has_moved);
+        c.run_frames(60, |c| update_inner(c, &mut s, &mut f));
+        assert_eq!(Some(world.x), drawn_x(&c));
+    }
+
+    #[test]
+    fn turn_ends_only_when_idle() {
+        let (mut c, mut s, mut f) = setup();
+        let start = Pos::new(5, 11);
+        let key = actor_at(&s, start).unwrap();
+        c.set_mouse(tile_center(start));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        c.set_mouse(tile_center(Pos::new(5, 13)));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+
+        // neither while moving nor while the menu is open
+        c.press_key(input::Key::E);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Moving(_)));
+        c.run_frames(300, |c| update_inner(c, &mut s, &mut f));
+        c.press_key(input::Key::E);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Confirm(k, _) if k == key));
+        assert_eq!(0, s.g.turn);
+
+        c.press_key(input::Key::Escape);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        c.press_key(input::Key::E);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert_eq!(1, s.g.turn);
+        assert!(matches!(s.g.selection, Selection::None));
+        assert!(!f.menu.focused);
+    }
+
+    #[test]
+    fn undo_a_move() {
+        let (mut c, mut s, mut f) = setup();
+        let start = Pos::new(5, 11);
+        let key = actor_at(&s, start).unwrap();
+        c.set_mouse(tile_center(start));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        c.set_mouse(tile_center(Pos::new(5, 13)));
+        c.press(Button::MouseLeft);
+        c.run_frames(300, |c| update_inner(c, &mut s, &mut f));
+
+        // the menu has the keyboard, escape takes the move back
+        c.press_key(input::Key::Escape);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Selected(k) if k == key));
+        assert_eq!(start, s.g.actors[key].pos);
+        assert_eq!(game_to_world(start), s.g.actors[key].draw_pos);
+        assert!(!s.g.actors[key].has_moved);
+        assert!(!f.menu.focused);
+    }
+
+    #[test]
+    fn sounds_load_once() {
+        let (mut c, mut s, mut f) = setup();
+        c.run_frames(3, |c| update_inner(c, &mut s, &mut f));
+        let names: Vec<_> = c.loaded_sounds.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(SOUNDS.to_vec(), names);
+        assert_eq!(("music".into(), "../assets/sound/music.wav".into()), c.loaded_sounds[3]);
+        assert_eq!(Some("music".into()), c.music);
+
+        // started once, the game doesn't restart it every frame
+        c.stop_music();
+        c.run_frames(3, |c| update_inner(c, &mut s, &mut f));
+        assert_eq!(None, c.music);
+        assert_eq!(SOUNDS.len(), c.loaded_sounds.len());
+    }
+
+    #[test]
+    fn offscreen_work_is_skipped() {
+        let (mut c, mut s, mut f) = setup();
+        c.view = Rect::new(0., 0., 8. * GRIDSIZE, 16. * GRIDSIZE);
+        let drawn_at = |c: &HeadlessContext, sprite: &Sprite, pos: Pos| {
+            let p = game_to_world(pos);
+            let call = DrawCall::TexturePart {
+                name: "tiles".into(),
+                src: sprite.src,
+                x: p.x,
+                y: p.y,
+                params: DrawParams::default(),
+            };
+            c.draws.iter().any(|d| d.call == call)
+        };
+
+        c.set_mouse(tile_center(Pos::new(5, 11)));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        let infantry = &s.sprites[&s.g.actors[actor_at(&s, Pos::new(5, 11)).unwrap()].sprite];
+        assert!(drawn_at(&c, infantry, Pos::new(5, 11)));
+        assert!(!drawn_at(&c, infantry, Pos::new(9, 11)));
+
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        let range = &s.sprites["move_range"];
+        assert!(drawn_at(&c, range, Pos::new(7, 11)));
+        assert!(!drawn_at(&c, range, Pos::new(8, 11)));
+    }
+
+    #[test]
+    fn enemies_can_not_be_selected() {
+        let (mut c, mut s, mut f) = setup();
+        let enemy = Pos::new(5, 3);
+        assert!(actor_at(&s, enemy).is_some());
+
+        c.set_mouse(tile_center(enemy));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::None));
+    }
+
+    fn snapshot(name: &str, c: &HeadlessContext) {
+        let path = format!("{}/snapshots/{name}.snap", env!("CARGO_MANIFEST_DIR"));
+        assert_snapshot(path, &c.snapshot());
+    }
+
+    #[test]
+    fn move_path_snapshot() {
+        let (mut c, s, _f) = setup();
+        let path = [(2, 2), (2, 3), (3, 3), (4, 3), (4, 2), (4, 1), (3, 1)].map(Pos::from);
+        c.frame(|c| draw_move_path(c, &s, &path));
+        snapshot("move_path", &c);
+    }
+
+    #[test]
+    fn nine_patch_snapshot() {
+        let (mut c, _s, _f) = setup();
+        c.frame(|c| ui::background().draw(c, Rect::new(10., 20., 60., 30.), 100));
+        snapshot("nine_patch", &c);
+    }
+
+    #[test]
+    fn keyboard_only_move() {
+        let (mut c, mut s, mut f) = setup();
+        let start = Pos::new(5, 11);
+        let goal = Pos::new(5, 13);
+        let key = actor_at(&s, start).unwrap();
+        let mut frame = |c: &mut HeadlessContext| c.frame(|c| update_inner(c, &mut s, &mut f));
+
+        c.press_key(input::Key::Tab);
+        frame(&mut c);
+        c.press_key(input::Key::Down);
+        frame(&mut c);
+        c.press_key(input::Key::S);
+        frame(&mut c);
+        c.press_key(input::Key::Enter);
+        frame(&mut c);
+        c.run_frames(300, &mut frame);
+        c.press_key(input::Key::Enter);
+        frame(&mut c);
+        assert_eq!(goal, s.g.actors[key].pos);
+        assert!(s.g.actors[key].has_moved);
+
+        // moved units are skipped
+        c.press_key(input::Key::Tab);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Selected(k) if k != key));
+        c.press_key(input::Key::Escape);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::None));
+
+        c.press_key(input::Key::E);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert_eq!(1, s.g.turn);
+        assert!(!s.g.actors[key].has_moved);
+    }
+
+    #[test]
+    fn drag_to_move() {
+        let (mut c, mut s, mut f) = setup();
+        let start = Pos::new(5, 11);
+        let goal = Pos::new(5, 13);
+        let key = actor_at(&s, start).unwrap();
+
+        c.set_mouse(tile_center(start));
+        c.hold(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Selected(k) if k == key));
+
+        c.set_mouse(tile_center(goal));
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Selected(_)));
+
+        c.release(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(matches!(s.g.selection, Selection::Moving(_)));
+        c.run_frames(300, |c| update_inner(c, &mut s, &mut f));
+        assert_eq!(goal, s.g.actors[key].pos);
+    }
+
+    #[test]
+    fn double_click_shows_info() {
+        let (mut c, mut s, mut f) = setup();
+        let enemy = Pos::new(5, 3);
+        let key = actor_at(&s, enemy).unwrap();
+
+        c.set_mouse(tile_center(enemy));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(f.info.is_none());
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(f.info == Some(key));
+        assert!(c.snapshot().contains("text \"HP 10/10\""));
+
+        // a single click anywhere closes it again
+        c.run_frames(30, |c| update_inner(c, &mut s, &mut f));
+        c.press(Button::MouseLeft);
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        assert!(f.info.is_none());
+    }
+
+    /// covers the tile layers, actors, cursor and buttons
+    #[test]
+    fn first_frame_snapshot() {
+        let (mut c, mut s, mut f) = setup();
+        c.set_mouse(tile_center(Pos::new(7, 7)));
+        c.frame(|c| update_inner(c, &mut s, &mut f));
+        snapshot("first_frame", &c);
+    }
 }
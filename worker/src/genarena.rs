@@ -17,12 +17,27 @@ pub struct Key<T> {
 
 impl<T> Clone for Key<T> {
     fn clone(&self) -> Key<T> {
-        Self { gen: self.gen, index: self.index, _type: PhantomData }
+        *self
     }
 }
 
 impl<T> Copy for Key<T> {}
 
+impl<T> PartialEq for Key<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.gen == other.gen && self.index == other.index
+    }
+}
+
+impl<T> Eq for Key<T> {}
+
+impl<T> std::hash::Hash for Key<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.gen.hash(state);
+        self.index.hash(state);
+    }
+}
+
 enum Content<T> {
     Filled(T),
     Empty(usize), // next free index
@@ -43,7 +58,7 @@ impl<T> GenArena<T> {
             let index = self.slots.len();
             self.slots.push(Slot { gen: 0, content: Content::Filled(x) });
             self.next_free += 1;
-            return Key { gen: 0, index: index as u32, _type: PhantomData };
+            Key { gen: 0, index: index as u32, _type: PhantomData }
         } else {
             let index = self.next_free;
             let slot = &mut self.slots[index];
@@ -55,7 +70,7 @@ impl<T> GenArena<T> {
             };
             slot.gen += 1;
             slot.content = Content::Filled(x);
-            return Key { index: index as u32, gen: slot.gen, _type: PhantomData };
+            Key { index: index as u32, gen: slot.gen, _type: PhantomData }
         }
     }
 
@@ -77,10 +92,10 @@ impl<T> GenArena<T> {
         if slot.gen != key.gen {
             return None;
         }
-        return match slot.content {
+        match slot.content {
             Content::Empty(_) => None,
             Content::Filled(ref x) => Some(x),
-        };
+        }
     }
 
     pub fn get_mut(&mut self, key: Key<T>) -> Option<&mut T> {
@@ -89,10 +104,10 @@ impl<T> GenArena<T> {
         if slot.gen != key.gen {
             return None;
         }
-        return match slot.content {
+        match slot.content {
             Content::Empty(_) => None,
             Content::Filled(ref mut x) => Some(x),
-        };
+        }
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &T> {
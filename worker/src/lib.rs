@@ -4,12 +4,18 @@ use base::{ContextTrait, PersistWrapper};
 use fleeting::FleetingState;
 use game::update_inner;
 use persistent::PersistentState;
+mod animation;
+mod cutscene;
 mod dijkstra;
 mod fleeting;
+mod frame;
 mod game;
 mod genarena;
 mod persistent;
+mod rebind;
 mod sprite;
+mod tween;
+mod ui;
 mod util;
 
 pub const GRIDSIZE: f32 = 16.;
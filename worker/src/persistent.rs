@@ -2,12 +2,14 @@ use std::collections::HashMap;
 
 use base::{
     grids::Grid,
-    ldtk::{grid_from_layer, EntityDef, EntityOnMap, GroundType, TerrainType, LDTK},
-    FPos, Rect,
+    ldtk::{grid_from_layer, GroundType, TerrainType, LDTK},
+    FPos, Rect, Tile,
 };
 use nanoserde::DeJson;
 
 use crate::{
+    animation::{self, Animation},
+    cutscene::{self, Cutscene},
     game::GameState,
     sprite::{self, Sprite},
     GRIDSIZE,
@@ -17,21 +19,17 @@ use crate::{
 pub struct PersistentState {
     // ######### BEGIN: data loaded from assets #########
     pub sprites: HashMap<String, Sprite>,
+    pub animations: HashMap<String, Animation>,
+    pub cutscenes: Vec<Cutscene>,
     pub ground: Grid<GroundType>,
     pub terrain: Grid<TerrainType>,
     pub ground_tiles: Vec<Tile>,
     pub terrain_tiles: Vec<Tile>,
     // ######### END  : data loaded from assets #########
-    /// need to smuggle this into coroutines and have not found a better way
-    pub delta: f32,
-
     /// the actual game state we would save
     pub g: GameState,
-}
-
-pub struct Tile {
-    pub source_rect: Rect,
-    pub pos: FPos,
+    /// sounds are queued for loading and the music runs
+    pub audio_started: bool,
 }
 
 impl PersistentState {
@@ -48,8 +46,7 @@ impl PersistentState {
                 .levels
                 .iter()
                 .flat_map(|level| level.layers.iter())
-                .filter(|layer| layer.id == "groundgrid")
-                .next()
+                .find(|layer| layer.id == "groundgrid")
                 .unwrap();
             ground = grid_from_layer(layer, |i| match i {
                 1 => GroundType::Ground,
@@ -77,12 +74,11 @@ impl PersistentState {
                 .levels
                 .iter()
                 .flat_map(|level| level.layers.iter())
-                .filter(|layer| layer.id == "infrastructuregrid")
-                .next()
+                .find(|layer| layer.id == "infrastructuregrid")
                 .unwrap();
             terrain = grid_from_layer(layer, |i| match i {
                 0 => TerrainType::None,
-                1 | 2 | 3 | 4 => TerrainType::Street,
+                1..=4 => TerrainType::Street,
                 5 => TerrainType::Forest,
                 _ => panic!("unsupported terrain type {}", i),
             });
@@ -107,12 +103,14 @@ impl PersistentState {
 
         Self {
             sprites: sprite::load_sprites("../assets/sprites.json"),
+            animations: animation::load_animations("../assets/animations.json").unwrap(),
+            cutscenes: cutscene::load_cutscenes("../assets/cutscenes.json"),
             ground,
             terrain,
             ground_tiles,
             terrain_tiles,
             g,
-            delta: 0.0,
+            audio_started: false,
         }
     }
 }
@@ -0,0 +1,176 @@
+use base::{
+    input::{Action, Binding, InputMap, Key},
+    text::TextStyle,
+    Color, ContextTrait, Space,
+};
+
+use crate::{
+    persistent::PersistentState,
+    tween::{Ease, Tween},
+    ui::{self, Menu},
+};
+
+/// in-game screen for rebinding the actions, toggled with F1
+/// F1 itself is not rebindable, so the screen can't be locked out
+#[derive(Default)]
+pub struct RebindScreen {
+    pub open: bool,
+    /// horizontal offset while sliding in after opening
+    slide: Option<Tween<f32>>,
+    menu: Menu,
+    /// waiting for the input which gets bound to the selected action
+    capturing: bool,
+    message: Option<String>,
+}
+
+const X: f32 = 30.;
+const Y: f32 = 30.;
+/// conflicts are reported in red
+const WARNING: TextStyle = ui::TEXT.color(Color::rgb(0.8, 0.1, 0.1));
+
+impl RebindScreen {
+    /// returns true while the screen is open, the game should not handle input then
+    pub fn update(&mut self, c: &mut dyn ContextTrait, s: &PersistentState) -> bool {
+        if c.is_key_pressed(Key::F1) && !self.capturing {
+            self.open = !self.open;
+            self.message = None;
+            self.slide = Some(Tween::new(-120., 0., 0.3, Ease::BackOut));
+        }
+        if !self.open {
+            return false;
+        }
+
+        let mut map = c.input_map();
+        // the input that ends capturing must not reach the menu as well
+        let was_capturing = self.capturing;
+        if self.capturing {
+            if c.is_key_pressed(Key::Escape) {
+                self.capturing = false;
+            } else if let Some(binding) =
+                c.last_input().filter(|b| *b != Binding::Key(Key::F1))
+            {
+                let others = map.bind(Action::ALL[self.menu.selected], binding);
+                self.message = (!others.is_empty()).then(|| {
+                    let labels: Vec<&str> = others.iter().map(|a| a.label()).collect();
+                    format!("{binding} is also used for {}", labels.join(", "))
+                });
+                c.set_input_map(map.clone());
+                self.capturing = false;
+            }
+        } else if c.is_action_pressed(Action::Cancel) {
+            self.open = false;
+        }
+
+        self.menu.focused = !was_capturing;
+        let activated = self.draw(c, s, &map);
+        if activated.is_some() && !was_capturing {
+            self.capturing = true;
+            self.message = None;
+        }
+        true
+    }
+
+    fn draw(
+        &mut self,
+        c: &mut dyn ContextTrait,
+        s: &PersistentState,
+        map: &InputMap,
+    ) -> Option<usize> {
+        let z = 250;
+        c.set_space(Space::Screen);
+        let conflicts = map.conflicts();
+        let x = X + self.slide.as_mut().map_or(0., |it| it.advance(c.delta()));
+        let title = ui::label(c, "Controls (F1 to close)", &ui::TEXT, x, Y, z).rect;
+
+        let entries: Vec<String> = Action::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                if self.capturing && i == self.menu.selected {
+                    return format!("{}: press a key or button", action.label());
+                }
+                let bindings: Vec<String> = map
+                    .get(*action)
+                    .map(|b| b.bindings().map(|b| b.to_string()).collect())
+                    .unwrap_or_default();
+                let conflicting =
+                    conflicts.iter().any(|(_, actions)| actions.contains(action));
+                let marker = if conflicting { "! " } else { "" };
+                format!("{marker}{}: {}", action.label(), bindings.join(", "))
+            })
+            .collect();
+        let menu_y = Y + ui::ROW_HEIGHT * 1.5;
+        let menu = self.menu.show(c, &s.sprites["pointer"], &entries, x, menu_y, z);
+        let mut panel = title.fuse(menu.rect);
+
+        if let Some(message) = &self.message {
+            let y = menu.rect.y + menu.rect.h + ui::ROW_HEIGHT * 0.5;
+            panel = panel.fuse(ui::label(c, message, &WARNING, x, y, z).rect);
+        }
+        ui::panel(c, panel, z);
+        c.set_space(Space::World);
+        menu.activated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::headless::HeadlessContext;
+
+    use super::*;
+
+    fn setup() -> (HeadlessContext, PersistentState) {
+        let mut c = HeadlessContext::new();
+        c.add_texture("tiles", 192., 176.);
+        c.add_texture("ui_bg", 192., 64.);
+        (c, PersistentState::new())
+    }
+
+    #[test]
+    fn rebind_with_keyboard() {
+        let (mut c, s) = setup();
+        let mut screen = RebindScreen::default();
+        let mut frame = |c: &mut HeadlessContext| c.frame(|c| _ = screen.update(c, &s));
+
+        for key in [Key::F1, Key::Down, Key::Enter, Key::Q] {
+            c.press_key(key);
+            frame(&mut c);
+        }
+        assert_eq!(vec![Key::Q], c.input_map.get(Action::Cancel).unwrap().keys);
+        assert!(c.input_map.conflicts().is_empty());
+
+        // conflicts are reported
+        for key in [Key::Enter, Key::E] {
+            c.press_key(key);
+            frame(&mut c);
+        }
+        assert_eq!(
+            vec![(Binding::Key(Key::E), vec![Action::Cancel, Action::EndTurn])],
+            c.input_map.conflicts()
+        );
+        assert!(c.snapshot().contains("E is also used for End turn"));
+        assert!(c.snapshot().contains("! Cancel: E, MouseRight"));
+
+        c.press_key(Key::F1);
+        frame(&mut c);
+        assert!(c.draws.is_empty());
+    }
+
+    #[test]
+    fn f1_is_not_captured() {
+        let (mut c, s) = setup();
+        let mut screen = RebindScreen::default();
+        let mut frame = |c: &mut HeadlessContext| c.frame(|c| _ = screen.update(c, &s));
+
+        for key in [Key::F1, Key::Down, Key::Enter, Key::F1] {
+            c.press_key(key);
+            frame(&mut c);
+        }
+        assert_eq!(InputMap::default(), c.input_map);
+        assert!(c.snapshot().contains("Cancel: press a key or button"));
+
+        c.press_key(Key::Q);
+        frame(&mut c);
+        assert_eq!(vec![Key::Q], c.input_map.get(Action::Cancel).unwrap().keys);
+    }
+}
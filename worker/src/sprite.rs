@@ -1,19 +1,30 @@
 use std::collections::HashMap;
 
-use base::{ContextTrait, Rect};
+use base::{ContextTrait, DrawParams, Rect};
 use nanoserde::DeJson;
 
 use crate::GRIDSIZE;
 
 #[derive(Clone)]
 pub struct Sprite {
-    src: Rect,
+    pub src: Rect,
 }
 
 impl Sprite {
     pub fn draw(&self, c: &mut dyn ContextTrait, x: f32, y: f32, z_level: i32) {
         c.draw_texture_part("tiles", self.src, x, y, z_level)
     }
+
+    pub fn draw_ex(
+        &self,
+        c: &mut dyn ContextTrait,
+        x: f32,
+        y: f32,
+        params: &DrawParams,
+        z_level: i32,
+    ) {
+        c.draw_texture_ex("tiles", self.src, x, y, params, z_level)
+    }
 }
 
 #[derive(DeJson, Debug)]
@@ -28,17 +39,7 @@ pub fn load_sprites(path: &str) -> HashMap<String, Sprite> {
     loaded
         .into_iter()
         .map(|(k, v)| {
-            (
-                k,
-                Sprite {
-                    src: Rect {
-                        x: v.x as _,
-                        y: v.y as _,
-                        w: GRIDSIZE as f32,
-                        h: GRIDSIZE as f32,
-                    },
-                },
-            )
+            (k, Sprite { src: Rect { x: v.x as _, y: v.y as _, w: GRIDSIZE, h: GRIDSIZE } })
         })
         .collect()
 }
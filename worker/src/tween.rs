@@ -0,0 +1,137 @@
+use base::{Color, FPos};
+use cosync::CosyncInput;
+
+use crate::{frame::FrameChannel, persistent::PersistentState};
+
+/// maps linear progress between 0 and 1 to eased progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ease {
+    #[default]
+    Linear,
+    /// overshoots a little before settling
+    BackOut,
+}
+
+impl Ease {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Ease::Linear => t,
+            Ease::BackOut => {
+                let (c1, c3) = (1.70158, 2.70158);
+                1. + c3 * (t - 1.).powi(3) + c1 * (t - 1.).powi(2)
+            }
+        }
+    }
+}
+
+/// values a tween can move between
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for FPos {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        FPos::lerp(self, to, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Color {
+            r: self.r.lerp(to.r, t),
+            g: self.g.lerp(to.g, t),
+            b: self.b.lerp(to.b, t),
+            a: self.a.lerp(to.a, t),
+        }
+    }
+}
+
+/// moves from one value to another over `duration` seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub ease: Ease,
+    elapsed: f32,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, ease: Ease) -> Self {
+        Tween { from, to, duration, ease, elapsed: 0. }
+    }
+
+    /// moves time forward and returns the new value
+    pub fn advance(&mut self, delta: f32) -> T {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        self.value()
+    }
+
+    pub fn value(&self) -> T {
+        let t = if self.duration > 0. { self.elapsed / self.duration } else { 1. };
+        self.from.lerp(self.to, self.ease.apply(t))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// drives the tween once per tick until it is done, `apply` writes the value into the state
+/// the first value is applied right away, the last one is exactly `to`
+/// time comes from the frame input, see [FrameChannel]
+pub async fn play<T: Lerp>(
+    input: &mut CosyncInput<PersistentState>,
+    frame: &FrameChannel,
+    mut tween: Tween<T>,
+    mut apply: impl FnMut(&mut PersistentState, T),
+) {
+    loop {
+        {
+            let value = tween.advance(frame.input().delta);
+            let s = &mut input.get();
+            apply(s, value);
+        }
+        if tween.is_finished() {
+            break;
+        }
+        cosync::sleep_ticks(1).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_endpoints() {
+        use Ease::*;
+        for ease in [Linear, BackOut] {
+            assert_eq!(0., ease.apply(0.), "{ease:?}");
+            assert!((1. - ease.apply(1.)).abs() < 1e-6, "{ease:?}");
+        }
+        assert_eq!(0.5, Linear.apply(0.5));
+        assert!(BackOut.apply(0.8) > 1.);
+    }
+
+    #[test]
+    fn advances_to_the_end() {
+        let from = FPos { x: 0., y: 10. };
+        let to = FPos { x: 10., y: 0. };
+        let mut tween = Tween::new(from, to, 0.5, Ease::Linear);
+        assert_eq!(FPos { x: 5., y: 5. }, tween.advance(0.25));
+        assert!(!tween.is_finished());
+        assert_eq!(to, tween.advance(1.));
+        assert!(tween.is_finished());
+
+        let mut fade = Tween::new(Color::WHITE, Color::rgba(1., 1., 1., 0.), 0., Ease::Linear);
+        assert_eq!(0., fade.advance(0.).a);
+    }
+}
@@ -0,0 +1,212 @@
+//! immediate mode widgets, they draw and report input in the same call
+//! meant for screen space, see [ContextTrait::set_space]
+
+use base::{
+    input::Action,
+    nine_patch::{Insets, NinePatch},
+    text::{HAlign, TextStyle, VAlign},
+    Button, Color, ContextTrait, Rect,
+};
+
+use crate::sprite::Sprite;
+
+/// labels are anchored at the left end of their vertical center
+pub const TEXT: TextStyle = TextStyle::new(6.).align(HAlign::Left, VAlign::Middle);
+pub const ROW_HEIGHT: f32 = 9.;
+/// space between content and the border of panels and buttons
+pub const PADDING: f32 = 5.;
+
+/// what happened to a widget this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Response {
+    pub rect: Rect,
+    pub hovered: bool,
+    pub clicked: bool,
+}
+
+impl Response {
+    fn new(c: &dyn ContextTrait, rect: Rect) -> Self {
+        let hovered = rect.contains(c.mouse_screen());
+        Response { rect, hovered, clicked: hovered && c.is_pressed(Button::MouseLeft) }
+    }
+}
+
+/// text in a row starting at x, y is the top of the row
+pub fn label(
+    c: &mut dyn ContextTrait,
+    text: &str,
+    style: &TextStyle,
+    x: f32,
+    y: f32,
+    z_level: i32,
+) -> Response {
+    let r = c.draw_text(text, style, x, y + ROW_HEIGHT / 2., z_level);
+    Response::new(c, r)
+}
+
+/// frame of panels, the corners are as big as the padding
+pub fn background() -> NinePatch {
+    NinePatch::new("ui_bg", Insets::all(PADDING))
+}
+
+/// background behind the content rect, drawn below z_level
+pub fn panel(c: &mut dyn ContextTrait, content: Rect, z_level: i32) -> Rect {
+    let r = content.grow_all(PADDING);
+    background().draw(c, r, z_level - 1);
+    r
+}
+
+/// framed text, the frame lights up while hovered
+pub fn button(c: &mut dyn ContextTrait, text: &str, x: f32, y: f32, z_level: i32) -> Response {
+    let text_rect = c.measure_text(text, &TEXT);
+    let inner = Rect::new(x, y, text_rect.w, ROW_HEIGHT).grow_all(PADDING - 2.);
+    let response = Response::new(c, inner.grow_all(2.));
+    let fill =
+        if response.hovered { Color::rgb(0.7, 0.7, 1.0) } else { Color::rgb(0.5, 0.5, 1.0) };
+    c.draw_rect(inner, fill, z_level - 1);
+    c.draw_rect(inner.grow_all(1.), Color::rgb(0.8, 0.8, 1.0), z_level - 2);
+    c.draw_rect(inner.grow_all(2.), Color::rgb(0., 0., 1.0), z_level - 3);
+    label(c, text, &TEXT, x, y, z_level);
+    response
+}
+
+/// text in a panel next to the mouse
+pub fn tooltip(c: &mut dyn ContextTrait, text: &str, z_level: i32) {
+    let mouse = c.mouse_screen();
+    let (x, y) = (mouse.x + 8., mouse.y + 8.);
+    let r = label(c, text, &TEXT, x + PADDING, y + PADDING, z_level).rect;
+    panel(c, r, z_level);
+}
+
+/// what happened to a menu this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MenuResponse {
+    /// covers all entries
+    pub rect: Rect,
+    pub hovered: Option<usize>,
+    /// clicked or confirmed with the keyboard
+    pub activated: Option<usize>,
+}
+
+/// vertical list of entries with a pointer at the selected one
+/// the selection follows the mouse, and the cursor actions while focused
+#[derive(Debug, Clone, Default)]
+pub struct Menu {
+    pub selected: usize,
+    /// only a focused menu reacts to the keyboard
+    pub focused: bool,
+}
+
+impl Menu {
+    pub fn show<S: AsRef<str>>(
+        &mut self,
+        c: &mut dyn ContextTrait,
+        pointer: &Sprite,
+        entries: &[S],
+        x: f32,
+        y: f32,
+        z_level: i32,
+    ) -> MenuResponse {
+        let len = entries.len();
+        let width =
+            entries.iter().map(|e| c.measure_text(e.as_ref(), &TEXT).w).fold(0., f32::max);
+        let rect = Rect::new(x, y, width, len as f32 * ROW_HEIGHT);
+
+        let mut area = rect;
+        let mut hovered = None;
+        let mut activated = None;
+        for (i, entry) in entries.iter().enumerate() {
+            let row = area.take_top(ROW_HEIGHT);
+            area = area.skip_top(ROW_HEIGHT);
+            let response = Response::new(c, row);
+            if response.hovered {
+                hovered = Some(i);
+                self.selected = i;
+            }
+            if response.clicked {
+                activated = Some(i);
+            }
+            label(c, entry.as_ref(), &TEXT, row.x, row.y, z_level);
+        }
+
+        if self.focused && len > 0 {
+            if c.is_action_pressed(Action::CursorUp) {
+                self.selected = (self.selected + len - 1) % len;
+            }
+            if c.is_action_pressed(Action::CursorDown) {
+                self.selected = (self.selected + 1) % len;
+            }
+            if c.is_action_pressed(Action::Confirm) {
+                activated = Some(self.selected);
+            }
+        }
+        self.selected = self.selected.min(len.saturating_sub(1));
+
+        let row = rect.skip_top(self.selected as f32 * ROW_HEIGHT).take_top(ROW_HEIGHT);
+        let center = row.center();
+        pointer.draw(c, row.x - 15., center.y - pointer.src.h / 2., z_level + 1);
+        MenuResponse { rect, hovered, activated }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{headless::HeadlessContext, input::Key, FPos};
+
+    use super::*;
+
+    fn pointer() -> Sprite {
+        Sprite { src: Rect::new(0., 0., 16., 16.) }
+    }
+
+    #[test]
+    fn menu_mouse_and_keyboard() {
+        let mut c = HeadlessContext::new();
+        c.add_texture("tiles", 192., 176.);
+        let mut menu = Menu::default();
+        let entries = ["one", "two", "three"];
+        let show = |c: &mut HeadlessContext, menu: &mut Menu| {
+            let mut response = None;
+            c.frame(|c| response = Some(menu.show(c, &pointer(), &entries, 10., 10., 5)));
+            response.unwrap()
+        };
+
+        // hovering selects, clicking activates
+        c.set_mouse(FPos { x: 12., y: 10. + ROW_HEIGHT * 1.5 });
+        c.press(Button::MouseLeft);
+        let response = show(&mut c, &mut menu);
+        assert_eq!(Some(1), response.hovered);
+        assert_eq!(Some(1), response.activated);
+        assert_eq!(1, menu.selected);
+
+        // unfocused menus ignore the keyboard
+        c.set_mouse(FPos { x: 0., y: 0. });
+        c.press_key(Key::Down);
+        let response = show(&mut c, &mut menu);
+        assert_eq!((None, None, 1), (response.hovered, response.activated, menu.selected));
+
+        menu.focused = true;
+        for key in [Key::Down, Key::Down] {
+            c.press_key(key);
+            show(&mut c, &mut menu);
+        }
+        assert_eq!(0, menu.selected);
+        c.press_key(Key::Enter);
+        assert_eq!(Some(0), show(&mut c, &mut menu).activated);
+    }
+
+    #[test]
+    fn button_reports_hover_and_click() {
+        let mut c = HeadlessContext::new();
+        c.add_texture("ui_bg", 192., 64.);
+        let mut response = None;
+        c.frame(|c| response = Some(button(c, "Go", 10., 10., 5)));
+        let r = response.unwrap();
+        assert!(!r.hovered && !r.clicked);
+
+        c.set_mouse(r.rect.center());
+        c.press(Button::MouseLeft);
+        c.frame(|c| response = Some(button(c, "Go", 10., 10., 5)));
+        assert!(response.unwrap().clicked);
+    }
+}
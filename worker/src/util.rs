@@ -1,5 +1,5 @@
 #![allow(unused)]
-use base::{FPos, Pos};
+use base::{FPos, Pos, Rect};
 
 use crate::GRIDSIZE;
 
@@ -22,3 +22,11 @@ pub fn game_to_world(p: Pos) -> FPos {
     let y = p.y as f32 * GRIDSIZE;
     FPos { x, y }
 }
+
+/// grid area covered by a world rect as (min, width, height), rounded outwards
+pub fn world_rect_to_game(r: Rect) -> (Pos, i32, i32) {
+    let min = Pos::new((r.x / GRIDSIZE).floor() as i32, (r.y / GRIDSIZE).floor() as i32);
+    let max_x = ((r.x + r.w) / GRIDSIZE).ceil() as i32;
+    let max_y = ((r.y + r.h) / GRIDSIZE).ceil() as i32;
+    (min, max_x - min.x, max_y - min.y)
+}